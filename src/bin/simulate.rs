@@ -0,0 +1,239 @@
+// Plays lots of card game fights without rendering anything and reports
+// how they went, to help with balancing.
+//
+// Run from the repository root so Settings.toml and the assets are found:
+//
+//   cargo run --bin simulate -- --fights 5000 --strategy random --format json
+//
+// Options:
+//   --fights N          number of fights to play (default 1000)
+//   --seed N            seed of the first fight, each fight after it
+//                       uses the next one (default random)
+//   --strategy NAME     how the player chooses what to do, random or
+//                       heuristic (default random)
+//   --format csv|json   how the report is written (default csv)
+//   --resolve N         override game__inner__starting_resolve
+//   --demon-power N     override game__inner__starting_demon_power
+
+use std::collections::BTreeMap;
+
+use rand::{thread_rng, Rng};
+use serde::Serialize;
+
+use inner_demons::inner::model::{
+    CardCatalogue, CardGameModel, CombatResult, PlayerAction, CARD_CATALOGUE_PATH, STARTING_DEMONS,
+};
+use inner_demons::inner::strategy::{self, Strategy};
+use inner_demons::settings::Settings;
+
+// Fights that go on longer than this are given up on
+const MAX_TURNS: u32 = 200;
+
+struct Options {
+    fights: u64,
+    seed: u64,
+    strategy: String,
+    format: Format,
+    resolve: Option<u32>,
+    demon_power: Option<u32>,
+}
+
+enum Format {
+    Csv,
+    Json,
+}
+
+impl Options {
+    fn from_args(mut args: impl Iterator<Item = String>) -> Options {
+        let mut options = Options {
+            fights: 1000,
+            seed: thread_rng().gen(),
+            strategy: "random".to_string(),
+            format: Format::Csv,
+            resolve: None,
+            demon_power: None,
+        };
+        while let Some(arg) = args.next() {
+            let mut value = || {
+                args.next()
+                    .unwrap_or_else(|| panic!("{} needs a value", arg))
+            };
+            match arg.as_str() {
+                "--fights" => options.fights = value().parse().expect("--fights must be a number"),
+                "--seed" => options.seed = value().parse().expect("--seed must be a number"),
+                "--strategy" => options.strategy = value(),
+                "--format" => {
+                    options.format = match value().as_str() {
+                        "csv" => Format::Csv,
+                        "json" => Format::Json,
+                        other => panic!("Unknown format {}", other),
+                    }
+                }
+                "--resolve" => {
+                    options.resolve = Some(value().parse().expect("--resolve must be a number"))
+                }
+                "--demon-power" => {
+                    options.demon_power =
+                        Some(value().parse().expect("--demon-power must be a number"))
+                }
+                other => panic!("Unknown option {}", other),
+            }
+        }
+        options
+    }
+}
+
+#[derive(Serialize)]
+struct Report {
+    strategy: String,
+    first_seed: u64,
+    fights: u64,
+    victories: u64,
+    defeats: u64,
+    // Fights still going after MAX_TURNS
+    unfinished: u64,
+    win_rate: f64,
+    average_turns: f64,
+    // Averaged over victories only, since defeats always end on zero
+    average_resolve_remaining: f64,
+    card_usage: Vec<CardUsage>,
+}
+
+#[derive(Serialize)]
+struct CardUsage {
+    card: String,
+    plays: u64,
+    plays_per_fight: f64,
+}
+
+struct FightOutcome {
+    result: Option<CombatResult>,
+    turns: u32,
+    resolve: u32,
+}
+
+fn play_fight(
+    model: &mut CardGameModel,
+    strategy: &mut dyn Strategy,
+    plays: &mut BTreeMap<String, u64>,
+) -> FightOutcome {
+    let mut turns = 0;
+    while model.result().is_none() && turns < MAX_TURNS {
+        let action = strategy.choose_action(model);
+        if let PlayerAction::Play { card_id, .. } = action {
+            if let Some(card) = model.hand.iter().find(|card| card.id == card_id) {
+                *plays.entry(card.kind.to_string()).or_default() += 1;
+            }
+        }
+        if let Err(err) = model.apply(&action) {
+            panic!(
+                "Strategy {} chose {:?}, which failed: {}",
+                strategy.name(),
+                action,
+                err
+            );
+        }
+        if let PlayerAction::EndTurn = action {
+            turns += 1;
+        }
+        // Nothing reads the updates, so don't let them pile up
+        model.drain_updates();
+    }
+    FightOutcome {
+        result: model.result(),
+        turns,
+        resolve: model.player_resolve,
+    }
+}
+
+fn main() {
+    let options = Options::from_args(std::env::args().skip(1));
+    let mut settings = Settings::from_config();
+    if let Some(resolve) = options.resolve {
+        settings.game.inner.starting_resolve = resolve;
+    }
+    if let Some(demon_power) = options.demon_power {
+        settings.game.inner.starting_demon_power = demon_power;
+    }
+    let catalogue = CardCatalogue::from_file(CARD_CATALOGUE_PATH)
+        .unwrap_or_else(|err| panic!("Failed to load card catalogue: {}", err));
+
+    let mut victories = 0;
+    let mut defeats = 0;
+    let mut unfinished = 0;
+    let mut total_turns = 0;
+    let mut total_resolve = 0;
+    let mut plays = BTreeMap::new();
+    for fight in 0..options.fights {
+        let seed = options.seed.wrapping_add(fight);
+        let mut strategy = strategy::by_name(&options.strategy, seed)
+            .unwrap_or_else(|| panic!("Unknown strategy {}", options.strategy));
+        let mut model = CardGameModel::new(
+            STARTING_DEMONS.to_vec(),
+            catalogue.starter_deck(),
+            &catalogue,
+            &settings,
+            seed,
+        );
+        let outcome = play_fight(&mut model, strategy.as_mut(), &mut plays);
+        total_turns += outcome.turns as u64;
+        match outcome.result {
+            Some(CombatResult::Victory) => {
+                victories += 1;
+                total_resolve += outcome.resolve as u64;
+            }
+            Some(CombatResult::Defeat) => defeats += 1,
+            None => unfinished += 1,
+        }
+    }
+
+    let fights = options.fights.max(1) as f64;
+    let report = Report {
+        strategy: options.strategy,
+        first_seed: options.seed,
+        fights: options.fights,
+        victories,
+        defeats,
+        unfinished,
+        win_rate: victories as f64 / fights,
+        average_turns: total_turns as f64 / fights,
+        average_resolve_remaining: total_resolve as f64 / victories.max(1) as f64,
+        card_usage: plays
+            .into_iter()
+            .map(|(card, plays)| CardUsage {
+                card,
+                plays,
+                plays_per_fight: plays as f64 / fights,
+            })
+            .collect(),
+    };
+    match options.format {
+        Format::Json => println!("{}", serde_json::to_string_pretty(&report).unwrap()),
+        Format::Csv => print_csv(&report),
+    }
+}
+
+// The summary and the card usage as two tables, separated by a blank line
+fn print_csv(report: &Report) {
+    println!("strategy,first_seed,fights,victories,defeats,unfinished,win_rate,average_turns,average_resolve_remaining");
+    println!(
+        "{},{},{},{},{},{},{:.4},{:.2},{:.2}",
+        report.strategy,
+        report.first_seed,
+        report.fights,
+        report.victories,
+        report.defeats,
+        report.unfinished,
+        report.win_rate,
+        report.average_turns,
+        report.average_resolve_remaining
+    );
+    println!();
+    println!("card,plays,plays_per_fight");
+    for usage in &report.card_usage {
+        println!(
+            "{},{},{:.2}",
+            usage.card, usage.plays, usage.plays_per_fight
+        );
+    }
+}
@@ -1,44 +1,207 @@
 use bevy::prelude::*;
 
-use self::model::{CardKind, DemonKind};
+use self::model::{Card, CombatResult, DrawError, Intent, Table, TurnPhase};
+use crate::state::AppState;
 
 pub struct CardGamePlugin;
 
 impl Plugin for CardGamePlugin {
     fn build(&self, app: &mut App) {
+        let catalogue = model::CardCatalogue::from_file(model::CARD_CATALOGUE_PATH)
+            .unwrap_or_else(|err| panic!("Failed to load card catalogue: {}", err));
         app.add_event::<CardGameEvent>()
-            .add_startup_system(model::setup)
-            .add_startup_system(view::setup)
-            .add_system(view::hand_card_interaction)
-            .add_system(view::end_turn_btn_interaction)
+            .add_event::<CardGameUpdate>()
+            .insert_resource(catalogue)
+            .init_resource::<view::Autoplay>()
+            .add_system(view::load_assets.in_schedule(OnEnter(AppState::Loading)))
+            .add_system(model::new_run.in_schedule(OnEnter(AppState::MainMenu)))
+            .add_system(view::setup_reward.in_schedule(OnEnter(AppState::Reward)))
+            .add_system(view::reward_interaction.in_set(OnUpdate(AppState::Reward)))
+            .add_system(view::teardown_reward.in_schedule(OnExit(AppState::Reward)))
+            // A fight only exists while the game is in the CardCombat state
+            .add_system(model::setup.in_schedule(OnEnter(AppState::CardCombat)))
+            .add_system(view::setup.in_schedule(OnEnter(AppState::CardCombat)))
+            .add_system(model::teardown.in_schedule(OnExit(AppState::CardCombat)))
+            .add_system(view::teardown.in_schedule(OnExit(AppState::CardCombat)))
             .add_system(
-                view::refresh_from_model
+                view::hand_card_interaction
+                    .run_if(model::is_not_replaying)
+                    .in_set(OnUpdate(AppState::CardCombat)),
+            )
+            .add_system(
+                view::end_turn_btn_interaction
+                    .run_if(model::is_not_replaying)
+                    .in_set(OnUpdate(AppState::CardCombat)),
+            )
+            .add_system(
+                view::demon_interaction
+                    .run_if(model::is_not_replaying)
+                    .in_set(OnUpdate(AppState::CardCombat)),
+            )
+            .add_system(
+                view::cancel_targeting
+                    .run_if(model::is_not_replaying)
+                    .in_set(OnUpdate(AppState::CardCombat)),
+            )
+            .add_system(
+                view::undo_interaction
+                    .run_if(model::is_not_replaying)
+                    .in_set(OnUpdate(AppState::CardCombat)),
+            )
+            // Autoplay can be toggled whatever the state
+            .add_system(view::autoplay_interaction.run_if(model::is_not_replaying))
+            .add_system(
+                view::autoplay
+                    .after(view::autoplay_interaction)
+                    .run_if(model::is_not_replaying)
+                    .in_set(OnUpdate(AppState::CardCombat)),
+            )
+            .add_system(view::autoplay_between_fights.after(view::autoplay_interaction))
+            // Replays are watched, not played
+            .add_system(
+                view::play_back_replay
+                    .run_if(model::is_replaying)
+                    .in_set(OnUpdate(AppState::CardCombat)),
+            )
+            .add_system(
+                model::send_events
                     .after(view::hand_card_interaction)
-                    .after(view::end_turn_btn_interaction),
+                    .after(view::end_turn_btn_interaction)
+                    .after(view::demon_interaction)
+                    .after(view::undo_interaction)
+                    .after(view::autoplay)
+                    .after(view::play_back_replay)
+                    .in_set(OnUpdate(AppState::CardCombat)),
+            )
+            .add_system(
+                model::log_events
+                    .after(model::send_events)
+                    .in_set(OnUpdate(AppState::CardCombat)),
+            )
+            .add_system(
+                view::queue_updates
+                    .after(model::send_events)
+                    .in_set(OnUpdate(AppState::CardCombat)),
+            )
+            .add_system(
+                view::present_updates
+                    .after(view::queue_updates)
+                    .in_set(OnUpdate(AppState::CardCombat)),
+            )
+            .add_system(
+                view::refresh_from_table
+                    .after(view::present_updates)
+                    .in_set(OnUpdate(AppState::CardCombat)),
+            )
+            .add_system(
+                view::refresh_hud
+                    .after(view::present_updates)
+                    .in_set(OnUpdate(AppState::CardCombat)),
+            )
+            .add_system(
+                view::refresh_demons
+                    .after(view::present_updates)
+                    .in_set(OnUpdate(AppState::CardCombat)),
+            )
+            .add_system(view::refresh_feedback.in_set(OnUpdate(AppState::CardCombat)))
+            .add_system(view::pile_interaction.in_set(OnUpdate(AppState::CardCombat)))
+            .add_system(
+                view::refresh_pile_viewer
+                    .after(view::pile_interaction)
+                    .after(view::present_updates)
+                    .in_set(OnUpdate(AppState::CardCombat)),
+            )
+            .add_system(
+                view::scroll_pile_viewer
+                    .after(view::refresh_pile_viewer)
+                    .in_set(OnUpdate(AppState::CardCombat)),
+            )
+            .add_system(
+                view::refresh_card_tooltip
+                    .after(view::refresh_from_table)
+                    .after(view::refresh_pile_viewer)
+                    .in_set(OnUpdate(AppState::CardCombat)),
+            )
+            .add_system(
+                view::finish_combat
+                    .after(view::present_updates)
+                    .in_set(OnUpdate(AppState::CardCombat)),
             );
     }
 }
 
+// Everything that happens to the card game model is announced with one
+// of these events, in the order it happened. Demons are referred to by
+// their index in the model's list of demons.
+#[derive(Clone, Debug)]
 pub enum CardGameEvent {
-    DrawCard,
-    DiscardCard(usize),
-    DiscardAll,
-    GainCard(CardKind),
-    PlayCard(usize),
+    DrawCard(Card),
+    // A card should have been drawn but couldn't be
+    DrawFailed(DrawError),
+    DiscardCard(Card),
+    ExhaustCard(Card),
+    GainCard(Card),
+    // A card was shuffled into the deck, so where it went is hidden
+    ShuffleCardIntoDeck(Card),
+    PlayCard(Card),
     ShuffleDiscardToDeck,
     Cleanup,
-    DemonAttack(DemonKind, u32),
+    DemonAttack(usize, u32),
     DamageResolve(u32),
-    ReduceStun(DemonKind),
+    ReduceStun(usize),
+    GainDefense(u32),
+    // Defense used up absorbing a demon attack
+    BlockDamage(u32),
+    // Defense lost at the start of a turn
+    LoseDefense(u32),
+    Heal(u32),
+    SpendComposure(u32),
+    RestoreComposure(u32),
+    WeakenDemon(usize, u32),
+    StunDemon(usize, u32),
+    // A demon has decided what it will do at the end of the turn
+    ChooseIntent(usize, Intent),
+    BuffDemon(usize, u32),
+    DemonDefend(usize, u32),
+    DamageDemon(usize, u32),
+    // The demon was removed from the list, so later indices shift down
+    DefeatDemon(usize),
+    CombatOver(CombatResult),
+    EnterPhase(TurnPhase),
+    // The last card played was taken back
+    Undo,
+    // The fight was loaded from a save
+    Resume,
+}
+
+// Whether a person is playing the card game rather than autoplay. Only
+// their fights are worth saving or recording
+pub fn is_not_autoplaying(autoplay: Res<view::Autoplay>) -> bool {
+    !autoplay.is_enabled()
+}
+
+// An event along with what the table looked like just after it
+// happened, so the view can present the game one step at a time
+#[derive(Clone, Debug)]
+pub struct CardGameUpdate {
+    pub event: CardGameEvent,
+    pub table: Table,
 }
 
 mod view {
 
-    use std::collections::HashMap;
+    use std::collections::{HashMap, VecDeque};
 
+    use bevy::input::mouse::{MouseScrollUnit, MouseWheel};
     use bevy::prelude::*;
+    use bevy::ui::FocusPolicy;
+    use bevy::window::PrimaryWindow;
+    use rand::{thread_rng, Rng};
 
-    use super::model;
+    use super::strategy::{HeuristicStrategy, Strategy};
+    use super::{model, CardGameEvent, CardGameUpdate};
+    use crate::settings::{PresentationSettings, Settings};
+    use crate::state::{AppState, LoadingHandles};
 
     #[derive(Resource, Clone)]
     pub struct ImageHandles {
@@ -54,7 +217,7 @@ mod view {
     }
 
     #[derive(Resource, Clone)]
-    struct FontHandles {
+    pub struct FontHandles {
         regular: Handle<Font>,
     }
 
@@ -77,6 +240,28 @@ mod view {
         hover: Handle<Image>,
     }
 
+    // Root of the card game's UI, despawned when the fight is over
+    #[derive(Component)]
+    pub struct CardGameRoot;
+
+    // How many cards the player gets to choose from after a victory
+    const REWARD_CHOICES: usize = 3;
+
+    const REWARD_TEXT_WIDTH: f32 = 200.0;
+
+    // The cards on offer after winning a fight
+    #[derive(Resource)]
+    pub struct RewardOffer {
+        choices: Vec<model::CardKind>,
+    }
+
+    #[derive(Component)]
+    pub struct RewardRoot;
+
+    // A card the player can take as a reward, or None to skip
+    #[derive(Component)]
+    pub struct RewardChoice(Option<model::CardKind>);
+
     #[derive(Component)]
     pub struct DeckArea;
 
@@ -92,6 +277,84 @@ mod view {
     #[derive(Component)]
     pub struct DiscardTop;
 
+    #[derive(Component)]
+    pub struct ExhaustArea;
+
+    #[derive(Component)]
+    pub struct ExhaustTop;
+
+    // The piles of cards off the table, which the player can look through
+    #[derive(Clone, Copy, Debug, Eq, PartialEq)]
+    pub enum Pile {
+        Deck,
+        Discard,
+        Exhaust,
+    }
+
+    impl Pile {
+        fn name(self) -> &'static str {
+            match self {
+                Pile::Deck => "Deck",
+                Pile::Discard => "Discard pile",
+                Pile::Exhaust => "Exhausted",
+            }
+        }
+
+        // The cards in the pile, in the order the player may see them
+        fn cards(self, table: &model::Table) -> Vec<model::Card> {
+            match self {
+                Pile::Deck => {
+                    // Sorted, so the order they'll be drawn in stays hidden
+                    let mut cards = table.deck.clone();
+                    cards.sort_by(|a, b| a.kind.0.cmp(&b.kind.0));
+                    cards
+                }
+                Pile::Discard => table.discard_pile.clone(),
+                Pile::Exhaust => table.exhaust_pile.clone(),
+            }
+        }
+    }
+
+    // Opens the pile viewer on a pile when clicked
+    #[derive(Component)]
+    pub struct PileButton(Pile);
+
+    // How many cards are in a pile
+    #[derive(Component)]
+    pub struct PileCount(Pile);
+
+    // The pile the player is looking through, if any
+    #[derive(Resource, Default)]
+    pub struct PileViewer {
+        pile: Option<Pile>,
+    }
+
+    #[derive(Component)]
+    pub struct PileViewerRoot;
+
+    #[derive(Component)]
+    pub struct PileViewerCloseBtn;
+
+    // The cards in the pile viewer, scrolled with the mouse wheel
+    #[derive(Component, Default)]
+    pub struct PileViewerGrid {
+        // How far the grid is scrolled up, in pixels
+        position: f32,
+    }
+
+    // A card shown in the pile viewer
+    #[derive(Component)]
+    pub struct PileViewerCard(model::Card);
+
+    // Explains the card under the mouse
+    #[derive(Component)]
+    pub struct CardTooltip;
+
+    #[derive(Component)]
+    pub struct CardTooltipText;
+
+    const TOOLTIP_WIDTH: f32 = 300.0;
+
     #[derive(Component)]
     pub struct PlayArea;
 
@@ -104,36 +367,121 @@ mod view {
     #[derive(Component)]
     pub struct EndTurnBtn;
 
-    pub fn setup(mut commands: Commands, asset_server: Res<AssetServer>) {
+    #[derive(Component)]
+    pub struct UndoBtn;
+
+    #[derive(Component)]
+    pub struct AutoplayBtn;
+
+    // The computer playing the card game in the player's place, kept
+    // between fights so it can be left running
+    #[derive(Resource)]
+    pub struct Autoplay {
+        enabled: bool,
+        strategy: HeuristicStrategy,
+        // Seconds until the next move
+        wait: f32,
+    }
+
+    impl Autoplay {
+        pub fn is_enabled(&self) -> bool {
+            self.enabled
+        }
+    }
+
+    impl FromWorld for Autoplay {
+        fn from_world(world: &mut World) -> Self {
+            Autoplay {
+                enabled: world.resource::<Settings>().game.inner.autoplay,
+                strategy: HeuristicStrategy::new(thread_rng().gen()),
+                wait: 0.0,
+            }
+        }
+    }
+
+    // Which of the player's numbers a piece of HUD text shows
+    #[derive(Component)]
+    pub enum HudText {
+        Resolve,
+        Defense,
+        Composure,
+    }
+
+    #[derive(Component)]
+    pub struct ResultText;
+
+    #[derive(Component)]
+    pub struct DemonArea;
+
+    // A demon that can be clicked on, by its index in the model
+    #[derive(Component)]
+    pub struct DemonButton(usize);
+
+    // The card waiting for the player to choose which demon it affects
+    #[derive(Resource, Default)]
+    pub struct Targeting {
+        card: Option<model::Card>,
+    }
+
+    // Tells the player why the game refused to do what they asked
+    #[derive(Resource, Default)]
+    pub struct Feedback {
+        message: String,
+        // Seconds left before the message is cleared
+        remaining: f32,
+    }
+
+    impl Feedback {
+        fn show(&mut self, message: String, settings: &PresentationSettings) {
+            info!("{}", message);
+            self.message = message;
+            self.remaining = settings.feedback;
+        }
+    }
+
+    #[derive(Component)]
+    pub struct FeedbackText;
+
+    // Updates from the model waiting to be shown to the player, and the
+    // state of the table as it is currently shown
+    #[derive(Resource, Default)]
+    pub struct Presentation {
+        queue: VecDeque<CardGameUpdate>,
+        shown: model::Table,
+        // Seconds left before the next update can be shown
+        remaining: f32,
+    }
+
+    impl Presentation {
+        // Whether the player is looking at the latest state of the model
+        pub fn is_idle(&self) -> bool {
+            self.queue.is_empty() && self.remaining <= 0.0
+        }
+    }
+
+    pub fn load_assets(
+        mut commands: Commands,
+        asset_server: Res<AssetServer>,
+        catalogue: Res<model::CardCatalogue>,
+        mut loading: ResMut<LoadingHandles>,
+    ) {
         // Load images
         let img_card_back = asset_server.load("images/Card Back.png");
-        let img_card_inspired = asset_server.load("images/Inspired.png");
-        let img_card_inspired_hover = asset_server.load("images/Card Back.png");
-        let img_card_peaceful = asset_server.load("images/Peaceful.png");
-        let img_card_peaceful_hover = asset_server.load("images/Card Back.png");
         let img_btn_end_turn = asset_server.load("images/end_turn_btn.png");
         let img_btn_end_turn_hover = asset_server.load("images/end_turn_btn_hover.png");
 
-        // Initialise card image handles
+        // Initialise card image handles from the card definitions
         let mut card_image_handles = HashMap::new();
-        // Inspired card
-        card_image_handles.insert(
-            model::CardKind::Inspired,
-            CardImageHandles {
-                face_up: img_card_inspired.clone(),
-                face_down: img_card_back.clone(),
-                hover: img_card_inspired_hover.clone(),
-            },
-        );
-        // Peaceful card
-        card_image_handles.insert(
-            model::CardKind::Peaceful,
-            CardImageHandles {
-                face_up: img_card_peaceful.clone(),
-                face_down: img_card_back.clone(),
-                hover: img_card_peaceful_hover.clone(),
-            },
-        );
+        for definition in catalogue.iter() {
+            card_image_handles.insert(
+                definition.name.clone(),
+                CardImageHandles {
+                    face_up: asset_server.load(definition.image.as_str()),
+                    face_down: img_card_back.clone(),
+                    hover: img_card_back.clone(),
+                },
+            );
+        }
 
         // Add image handles as resources
         let image_handles = ImageHandles {
@@ -144,18 +492,208 @@ mod view {
             cards: card_image_handles,
             card_back: img_card_back.clone(),
         };
-        commands.insert_resource(image_handles.clone());
+        loading.0.push(image_handles.card_back.clone_untyped());
+        loading
+            .0
+            .push(image_handles.end_turn_btn.normal.clone_untyped());
+        loading
+            .0
+            .push(image_handles.end_turn_btn.hover.clone_untyped());
+        for card_image_handles in image_handles.cards.values() {
+            loading.0.push(card_image_handles.face_up.clone_untyped());
+        }
+        commands.insert_resource(image_handles);
 
         // Load fonts
         let font_handles = FontHandles {
-            regular: asset_server.load("fonts/Kenney High.ttf"),
+            regular: asset_server.load("fonts/DejaVuSansCondensed.ttf"),
         };
-        commands.insert_resource(font_handles.clone());
+        loading.0.push(font_handles.regular.clone_untyped());
+        commands.insert_resource(font_handles);
+    }
+
+    pub fn setup(
+        mut commands: Commands,
+        font_handles: Res<FontHandles>,
+        image_handles: Res<ImageHandles>,
+    ) {
+        commands.insert_resource(Presentation::default());
+        commands.insert_resource(Targeting::default());
+        commands.insert_resource(Feedback::default());
+        commands.insert_resource(PileViewer::default());
         // Init UI
-        commands.spawn(Camera2dBundle::default());
         setup_ui(&mut commands, &font_handles, &image_handles);
     }
 
+    pub fn teardown(mut commands: Commands, q_root: Query<Entity, With<CardGameRoot>>) {
+        for root in &q_root {
+            commands.entity(root).despawn_recursive();
+        }
+        commands.remove_resource::<Presentation>();
+        commands.remove_resource::<Targeting>();
+        commands.remove_resource::<Feedback>();
+        commands.remove_resource::<PileViewer>();
+    }
+
+    // Move on once the player has seen how the fight ended
+    pub fn finish_combat(
+        mut commands: Commands,
+        presentation: Res<Presentation>,
+        mut game_model: ResMut<model::CardGameModel>,
+        mut next_state: ResMut<NextState<AppState>>,
+    ) {
+        if !presentation.is_idle() {
+            return;
+        }
+        match presentation.shown.result {
+            Some(model::CombatResult::Victory) => {
+                commands.insert_resource(RewardOffer {
+                    choices: game_model.reward_choices(REWARD_CHOICES),
+                });
+                next_state.set(AppState::Reward);
+            }
+            Some(model::CombatResult::Defeat) => next_state.set(AppState::GameOver),
+            None => {}
+        }
+    }
+
+    pub fn setup_reward(
+        mut commands: Commands,
+        offer: Res<RewardOffer>,
+        catalogue: Res<model::CardCatalogue>,
+        font_handles: Res<FontHandles>,
+        image_handles: Res<ImageHandles>,
+    ) {
+        let text_style = TextStyle {
+            font: font_handles.regular.clone(),
+            font_size: 40.0,
+            color: Color::WHITE,
+        };
+        let description_style = TextStyle {
+            font_size: 24.0,
+            ..text_style.clone()
+        };
+        commands
+            .spawn(NodeBundle {
+                style: Style {
+                    size: Size::all(Val::Percent(100.0)),
+                    flex_direction: FlexDirection::Column,
+                    justify_content: JustifyContent::Center,
+                    align_items: AlignItems::Center,
+                    ..default()
+                },
+                background_color: Color::rgb(0.1, 0.05, 0.2).into(),
+                ..default()
+            })
+            .insert(RewardRoot)
+            .with_children(|root| {
+                root.spawn(TextBundle::from_section(
+                    "Choose a card to keep",
+                    text_style.clone(),
+                ));
+                root.spawn(NodeBundle {
+                    style: Style {
+                        margin: UiRect::all(Val::Px(30.0)),
+                        ..default()
+                    },
+                    ..default()
+                })
+                .with_children(|choices| {
+                    for kind in &offer.choices {
+                        choices
+                            .spawn(NodeBundle {
+                                style: Style {
+                                    flex_direction: FlexDirection::ColumnReverse,
+                                    align_items: AlignItems::Center,
+                                    margin: UiRect::horizontal(Val::Px(20.0)),
+                                    ..default()
+                                },
+                                ..default()
+                            })
+                            .with_children(|choice| {
+                                // Many cards share their art, so say what
+                                // each one does under its name
+                                let description = catalogue
+                                    .get(kind)
+                                    .map(|definition| definition.description.as_str())
+                                    .unwrap_or_default();
+                                choice.spawn(
+                                    TextBundle::from_section(
+                                        description,
+                                        description_style.clone(),
+                                    )
+                                    .with_text_alignment(TextAlignment::Center)
+                                    .with_style(Style {
+                                        max_size: Size::width(Val::Px(REWARD_TEXT_WIDTH)),
+                                        ..default()
+                                    }),
+                                );
+                                choice.spawn(TextBundle::from_section(
+                                    kind.to_string(),
+                                    text_style.clone(),
+                                ));
+                                choice
+                                    .spawn(ButtonBundle {
+                                        style: Style {
+                                            size: Size::new(Val::Px(128.0), Val::Px(144.0)),
+                                            ..default()
+                                        },
+                                        image: UiImage {
+                                            texture: image_handles
+                                                .cards
+                                                .get(kind)
+                                                .unwrap()
+                                                .face_up
+                                                .clone(),
+                                            ..default()
+                                        },
+                                        ..default()
+                                    })
+                                    .insert(RewardChoice(Some(kind.clone())));
+                            });
+                    }
+                });
+                root.spawn(ButtonBundle {
+                    style: Style {
+                        size: Size::new(Val::Px(160.0), Val::Px(50.0)),
+                        justify_content: JustifyContent::Center,
+                        align_items: AlignItems::Center,
+                        ..default()
+                    },
+                    background_color: Color::rgb(0.3, 0.1, 0.7).into(),
+                    ..default()
+                })
+                .insert(RewardChoice(None))
+                .with_children(|skip| {
+                    skip.spawn(TextBundle::from_section("Skip", text_style.clone()));
+                });
+            });
+    }
+
+    pub fn reward_interaction(
+        q_choice: Query<(&Interaction, &RewardChoice), Changed<Interaction>>,
+        mut run_deck: ResMut<model::RunDeck>,
+        mut next_state: ResMut<NextState<AppState>>,
+    ) {
+        for (interaction, choice) in &q_choice {
+            if *interaction != Interaction::Clicked {
+                continue;
+            }
+            if let Some(kind) = &choice.0 {
+                info!("Added {} to the deck", kind);
+                run_deck.add(kind.clone());
+            }
+            next_state.set(AppState::Overworld);
+        }
+    }
+
+    pub fn teardown_reward(mut commands: Commands, q_root: Query<Entity, With<RewardRoot>>) {
+        for root in &q_root {
+            commands.entity(root).despawn_recursive();
+        }
+        commands.remove_resource::<RewardOffer>();
+    }
+
     fn setup_ui(commands: &mut Commands, font_handles: &FontHandles, image_handles: &ImageHandles) {
         // Root node of layout
         commands
@@ -168,6 +706,7 @@ mod view {
                 },
                 ..default()
             })
+            .insert(CardGameRoot)
             .with_children(|root| {
                 // Hand, deck and discard pile area
                 root.spawn(NodeBundle {
@@ -181,31 +720,15 @@ mod view {
                 })
                 .with_children(|dock| {
                     // Deck area
-                    dock.spawn(NodeBundle {
-                        style: Style {
-                            size: Size::width(Val::Px(138.0)),
-                            justify_content: JustifyContent::Center,
-                            ..default()
-                        },
-                        background_color: Color::rgb(0.3, 0.3, 0.3).into(),
-                        ..default()
-                    })
-                    .with_children(|deck_area| {
-                        deck_area
-                            .spawn(ImageBundle {
-                                style: Style {
-                                    size: Size::new(Val::Px(64.0), Val::Px(72.0)),
-                                    ..default()
-                                },
-                                image: UiImage {
-                                    texture: image_handles.card_back.clone(),
-                                    ..default()
-                                },
-                                ..default()
-                            })
-                            .insert(DeckTop);
-                    })
-                    .insert(DeckArea);
+                    spawn_pile_area(
+                        dock,
+                        Pile::Deck,
+                        DeckArea,
+                        DeckTop,
+                        Visibility::Inherited,
+                        font_handles,
+                        image_handles,
+                    );
                     // Hand area
                     dock.spawn(NodeBundle {
                         style: Style {
@@ -218,32 +741,25 @@ mod view {
                     })
                     .insert(HandArea);
                     // Discard pile area
-                    dock.spawn(NodeBundle {
-                        style: Style {
-                            size: Size::width(Val::Px(138.0)),
-                            justify_content: JustifyContent::Center,
-                            ..default()
-                        },
-                        background_color: Color::rgb(0.3, 0.3, 0.3).into(),
-                        ..default()
-                    })
-                    .insert(DiscardArea)
-                    .with_children(|discard_area| {
-                        discard_area
-                            .spawn(ImageBundle {
-                                style: Style {
-                                    size: Size::new(Val::Px(64.0), Val::Px(72.0)),
-                                    ..default()
-                                },
-                                image: UiImage {
-                                    texture: image_handles.card_back.clone(),
-                                    ..default()
-                                },
-                                visibility: Visibility::Hidden,
-                                ..default()
-                            })
-                            .insert(DiscardTop);
-                    });
+                    spawn_pile_area(
+                        dock,
+                        Pile::Discard,
+                        DiscardArea,
+                        DiscardTop,
+                        Visibility::Hidden,
+                        font_handles,
+                        image_handles,
+                    );
+                    // Exhaust pile area
+                    spawn_pile_area(
+                        dock,
+                        Pile::Exhaust,
+                        ExhaustArea,
+                        ExhaustTop,
+                        Visibility::Hidden,
+                        font_handles,
+                        image_handles,
+                    );
                 });
                 // HUD area 1
                 root.spawn(NodeBundle {
@@ -254,6 +770,29 @@ mod view {
                     background_color: Color::rgb(0.3, 0.1, 0.7).into(),
                     ..default()
                 })
+                .with_children(|hud1| {
+                    for hud_text in [HudText::Resolve, HudText::Defense, HudText::Composure] {
+                        hud1.spawn(
+                            TextBundle::from_section(
+                                "",
+                                TextStyle {
+                                    font: font_handles.regular.clone(),
+                                    font_size: 24.0,
+                                    color: Color::WHITE,
+                                },
+                            )
+                            .with_style(Style {
+                                margin: UiRect {
+                                    left: Val::Px(5.0),
+                                    right: Val::Px(15.0),
+                                    ..default()
+                                },
+                                ..default()
+                            }),
+                        )
+                        .insert(hud_text);
+                    }
+                })
                 .insert(HudArea1);
                 // Play area
                 root.spawn(NodeBundle {
@@ -277,6 +816,33 @@ mod view {
                     ..default()
                 })
                 .with_children(|hud2| {
+                    hud2.spawn(
+                        TextBundle::from_section(
+                            "",
+                            TextStyle {
+                                font: font_handles.regular.clone(),
+                                font_size: 32.0,
+                                color: Color::rgb(1.0, 0.8, 0.8),
+                            },
+                        )
+                        .with_style(Style {
+                            margin: UiRect::right(Val::Px(20.0)),
+                            align_self: AlignSelf::Center,
+                            ..default()
+                        }),
+                    )
+                    .insert(FeedbackText);
+                    hud2.spawn(TextBundle::from_section(
+                        "",
+                        TextStyle {
+                            font: font_handles.regular.clone(),
+                            font_size: 40.0,
+                            color: Color::WHITE,
+                        },
+                    ))
+                    .insert(ResultText);
+                    spawn_text_button(hud2, "Auto", font_handles, AutoplayBtn);
+                    spawn_text_button(hud2, "Undo", font_handles, UndoBtn);
                     hud2.spawn(ButtonBundle {
                         style: Style {
                             size: Size::width(Val::Px(120.0)),
@@ -300,19 +866,157 @@ mod view {
                 root.spawn(NodeBundle {
                     style: Style {
                         size: Size::height(Val::Px(400.0)),
+                        justify_content: JustifyContent::SpaceEvenly,
+                        align_items: AlignItems::Center,
                         ..default()
                     },
                     background_color: Color::rgb(0.8, 0.8, 0.8).into(),
                     ..default()
-                });
+                })
+                .insert(DemonArea);
+            });
+        // Card tooltip, over everything else and hidden until a card is
+        // hovered
+        commands
+            .spawn(NodeBundle {
+                style: Style {
+                    position_type: PositionType::Absolute,
+                    size: Size::width(Val::Px(TOOLTIP_WIDTH)),
+                    padding: UiRect::all(Val::Px(8.0)),
+                    ..default()
+                },
+                background_color: Color::rgba(0.1, 0.05, 0.2, 0.95).into(),
+                visibility: Visibility::Hidden,
+                z_index: ZIndex::Global(2),
+                ..default()
+            })
+            .insert((CardGameRoot, CardTooltip))
+            .with_children(|tooltip| {
+                tooltip
+                    .spawn(
+                        TextBundle::from_sections([
+                            TextSection::from_style(TextStyle {
+                                font: font_handles.regular.clone(),
+                                font_size: 32.0,
+                                color: Color::WHITE,
+                            }),
+                            TextSection::from_style(TextStyle {
+                                font: font_handles.regular.clone(),
+                                font_size: 20.0,
+                                color: Color::rgb(0.9, 0.9, 0.9),
+                            }),
+                        ])
+                        .with_style(Style {
+                            max_size: Size::width(Val::Px(TOOLTIP_WIDTH - 16.0)),
+                            ..default()
+                        }),
+                    )
+                    .insert(CardTooltipText);
+            });
+    }
+
+    fn spawn_text_button(
+        parent: &mut ChildBuilder,
+        label: &str,
+        font_handles: &FontHandles,
+        marker: impl Component,
+    ) {
+        parent
+            .spawn(ButtonBundle {
+                style: Style {
+                    size: Size::width(Val::Px(100.0)),
+                    margin: UiRect {
+                        left: Val::Px(5.0),
+                        top: Val::Px(5.0),
+                        ..default()
+                    },
+                    justify_content: JustifyContent::Center,
+                    align_items: AlignItems::Center,
+                    ..default()
+                },
+                background_color: TEXT_BUTTON_COLOR.into(),
+                ..default()
+            })
+            .insert(marker)
+            .with_children(|button| {
+                button.spawn(TextBundle::from_section(
+                    label,
+                    TextStyle {
+                        font: font_handles.regular.clone(),
+                        font_size: 32.0,
+                        color: Color::WHITE,
+                    },
+                ));
             });
     }
 
+    // A pile's area of the dock, showing its top card and how many cards
+    // it holds, which opens the pile viewer when clicked
+    fn spawn_pile_area(
+        dock: &mut ChildBuilder,
+        pile: Pile,
+        area: impl Component,
+        top: impl Component,
+        top_visibility: Visibility,
+        font_handles: &FontHandles,
+        image_handles: &ImageHandles,
+    ) {
+        dock.spawn(ButtonBundle {
+            style: Style {
+                size: Size::width(Val::Px(138.0)),
+                justify_content: JustifyContent::Center,
+                ..default()
+            },
+            background_color: Color::rgb(0.3, 0.3, 0.3).into(),
+            ..default()
+        })
+        .insert((area, PileButton(pile)))
+        .with_children(|pile_area| {
+            pile_area
+                .spawn(ImageBundle {
+                    style: Style {
+                        size: Size::new(Val::Px(64.0), Val::Px(72.0)),
+                        ..default()
+                    },
+                    image: UiImage {
+                        texture: image_handles.card_back.clone(),
+                        ..default()
+                    },
+                    visibility: top_visibility,
+                    ..default()
+                })
+                .insert(top);
+            pile_area
+                .spawn(
+                    TextBundle::from_section(
+                        "0",
+                        TextStyle {
+                            font: font_handles.regular.clone(),
+                            font_size: 32.0,
+                            color: Color::WHITE,
+                        },
+                    )
+                    .with_style(Style {
+                        position_type: PositionType::Absolute,
+                        position: UiRect {
+                            right: Val::Px(8.0),
+                            bottom: Val::Px(4.0),
+                            ..default()
+                        },
+                        ..default()
+                    }),
+                )
+                .insert(PileCount(pile));
+        });
+    }
+
+    const TEXT_BUTTON_COLOR: Color = Color::rgb(0.5, 0.3, 0.8);
+    const TEXT_BUTTON_ACTIVE_COLOR: Color = Color::rgb(0.9, 0.6, 0.2);
+
     fn create_card(card_model: model::Card, image_handles: &ImageHandles) -> CardBundle {
-        let kind = card_model.kind;
         let card = Card {
+            image_handles: image_handles.cards.get(&card_model.kind).unwrap().clone(),
             model: card_model,
-            image_handles: image_handles.cards.get(&kind).unwrap().clone(),
         };
         let start_texture = card.image_handles.face_up.clone();
         CardBundle {
@@ -335,19 +1039,103 @@ mod view {
         }
     }
 
-    pub fn refresh_from_model(
-        mut commands: Commands,
-        mut q_hand_area: Query<Entity, With<HandArea>>,
+    pub fn queue_updates(
+        mut ev_update: EventReader<CardGameUpdate>,
+        mut presentation: ResMut<Presentation>,
+    ) {
+        // Only showing an update counts as a change to the presentation
+        presentation
+            .bypass_change_detection()
+            .queue
+            .extend(ev_update.iter().cloned());
+    }
+
+    // How long the table is shown after an event before moving on
+    fn update_duration(event: &CardGameEvent, settings: &PresentationSettings) -> f32 {
+        match event {
+            CardGameEvent::DrawCard(_)
+            | CardGameEvent::DiscardCard(_)
+            | CardGameEvent::ExhaustCard(_)
+            | CardGameEvent::GainCard(_)
+            | CardGameEvent::ShuffleCardIntoDeck(_)
+            | CardGameEvent::PlayCard(_)
+            | CardGameEvent::Undo => settings.card_move,
+            CardGameEvent::ShuffleDiscardToDeck | CardGameEvent::Cleanup => settings.pile_move,
+            CardGameEvent::DemonAttack(_, _)
+            | CardGameEvent::DamageResolve(_)
+            | CardGameEvent::ReduceStun(_)
+            | CardGameEvent::BlockDamage(_)
+            | CardGameEvent::WeakenDemon(_, _)
+            | CardGameEvent::StunDemon(_, _)
+            | CardGameEvent::ChooseIntent(_, _)
+            | CardGameEvent::BuffDemon(_, _)
+            | CardGameEvent::DemonDefend(_, _)
+            | CardGameEvent::DamageDemon(_, _)
+            | CardGameEvent::DefeatDemon(_) => settings.demon_action,
+            CardGameEvent::CombatOver(_) => settings.combat_over,
+            CardGameEvent::GainDefense(_)
+            | CardGameEvent::LoseDefense(_)
+            | CardGameEvent::Heal(_)
+            | CardGameEvent::SpendComposure(_)
+            | CardGameEvent::RestoreComposure(_)
+            | CardGameEvent::DrawFailed(_) => settings.effect,
+            // Phases are only bookkeeping, what happens in them is shown
+            CardGameEvent::EnterPhase(_) | CardGameEvent::Resume => 0.0,
+        }
+    }
+
+    pub fn present_updates(
+        time: Res<Time>,
+        settings: Res<Settings>,
+        mut presentation: ResMut<Presentation>,
+    ) {
+        // The timer ticks every frame, but only a newly shown table
+        // should make the view refresh
+        let mut shown_changed = false;
+        let timing = presentation.bypass_change_detection();
+        timing.remaining -= time.delta_seconds();
+        while timing.remaining <= 0.0 {
+            match timing.queue.pop_front() {
+                Some(update) => {
+                    timing.remaining += update_duration(&update.event, &settings.presentation);
+                    timing.shown = update.table;
+                    shown_changed = true;
+                }
+                None => {
+                    // Don't bank time while there's nothing to show
+                    timing.remaining = 0.0;
+                    break;
+                }
+            }
+        }
+        if shown_changed {
+            presentation.set_changed();
+        }
+    }
+
+    pub fn refresh_from_table(
+        mut commands: Commands,
+        mut q_hand_area: Query<Entity, With<HandArea>>,
         mut q_play_area: Query<Entity, With<PlayArea>>,
-        mut q_deck_top_visibility: Query<&mut Visibility, (With<DeckTop>, Without<DiscardTop>)>,
+        mut q_deck_top_visibility: Query<
+            &mut Visibility,
+            (With<DeckTop>, Without<DiscardTop>, Without<ExhaustTop>),
+        >,
         mut q_disc_top: Query<
             (&mut Visibility, &mut UiImage),
-            (Without<DeckTop>, With<DiscardTop>),
+            (Without<DeckTop>, With<DiscardTop>, Without<ExhaustTop>),
+        >,
+        mut q_exhaust_top: Query<
+            (&mut Visibility, &mut UiImage),
+            (Without<DeckTop>, Without<DiscardTop>, With<ExhaustTop>),
         >,
+        mut q_pile_count: Query<(&mut Text, &PileCount)>,
         q_cards: Query<(Entity, &Card)>,
         image_handles: Res<ImageHandles>,
-        game_model: Res<model::CardGameModel>,
+        presentation: Res<Presentation>,
     ) {
+        let table = &presentation.shown;
+
         // Catalog the card objects that already exist in the scene
         // to avoid despawning and creating them every frame
         let mut visible_cards: HashMap<u32, Entity> = HashMap::new();
@@ -362,7 +1150,7 @@ mod view {
         {
             // Update the hand
             let mut hand_area = commands.entity(q_hand_area.single_mut());
-            for card in game_model.hand.iter() {
+            for card in table.hand.iter() {
                 match visible_cards.get(&card.id) {
                     Some(entity) => {
                         // Set card's parent as the hand area
@@ -381,7 +1169,7 @@ mod view {
         {
             // Update the play area
             let mut play_area = commands.entity(q_play_area.single_mut());
-            for card in game_model.in_play.iter() {
+            for card in table.in_play.iter() {
                 match visible_cards.get(&card.id) {
                     Some(entity) => {
                         // Set card's parent as the hand area
@@ -400,41 +1188,39 @@ mod view {
 
         // Update the deck
         let mut deck_top_visibility = q_deck_top_visibility.single_mut();
-        if game_model.deck.is_empty() {
+        if table.deck.is_empty() {
             *deck_top_visibility = Visibility::Hidden;
         } else {
             *deck_top_visibility = Visibility::Visible;
         }
 
-        // Update the discard pile
+        // Update the discard and exhaust piles
         let (mut disc_top_visibility, mut disc_top_image) = q_disc_top.single_mut();
-        if game_model.discard_pile.is_empty() {
-            *disc_top_visibility = Visibility::Hidden;
-        } else {
-            *disc_top_visibility = Visibility::Visible;
-            // Set the discard top's image to the card on top of the discard pile
-            let top_card_kind = game_model.discard_pile.last().unwrap().kind;
-            disc_top_image.texture = image_handles
-                .cards
-                .get(&top_card_kind)
-                .unwrap()
-                .face_up
-                .clone();
+        refresh_pile_top(
+            &table.discard_pile,
+            &mut disc_top_visibility,
+            &mut disc_top_image,
+            &image_handles,
+        );
+        let (mut exhaust_top_visibility, mut exhaust_top_image) = q_exhaust_top.single_mut();
+        refresh_pile_top(
+            &table.exhaust_pile,
+            &mut exhaust_top_visibility,
+            &mut exhaust_top_image,
+            &image_handles,
+        );
+        for (mut text, pile_count) in &mut q_pile_count {
+            text.sections[0].value = pile_count.0.cards(table).len().to_string();
         }
 
         {
-            // Clean up cards in either the deck or discard pile
+            // Clean up cards that have left the hand and play area
             for (card_entity, card) in q_cards.iter() {
-                if game_model
-                    .deck
+                if !table
+                    .hand
                     .iter()
-                    .find(|c| c.id == card.model.id)
-                    .is_some()
-                    || game_model
-                        .discard_pile
-                        .iter()
-                        .find(|c| c.id == card.model.id)
-                        .is_some()
+                    .chain(&table.in_play)
+                    .any(|c| c.id == card.model.id)
                 {
                     commands.entity(card_entity).despawn_recursive();
                 }
@@ -442,17 +1228,563 @@ mod view {
         }
     }
 
+    // Show the card on top of a face up pile, or nothing if it's empty
+    fn refresh_pile_top(
+        pile: &[model::Card],
+        visibility: &mut Visibility,
+        image: &mut UiImage,
+        image_handles: &ImageHandles,
+    ) {
+        match pile.last() {
+            Some(top_card) => {
+                *visibility = Visibility::Visible;
+                image.texture = image_handles
+                    .cards
+                    .get(&top_card.kind)
+                    .unwrap()
+                    .face_up
+                    .clone();
+            }
+            None => *visibility = Visibility::Hidden,
+        }
+    }
+
+    // Open the pile viewer on the pile clicked, or close it again
+    pub fn pile_interaction(
+        q_pile_button: Query<(&Interaction, &PileButton), Changed<Interaction>>,
+        q_close_button: Query<&Interaction, (Changed<Interaction>, With<PileViewerCloseBtn>)>,
+        keys: Res<Input<KeyCode>>,
+        mut viewer: ResMut<PileViewer>,
+    ) {
+        for (interaction, pile_button) in &q_pile_button {
+            if *interaction == Interaction::Clicked {
+                viewer.pile = if viewer.pile == Some(pile_button.0) {
+                    None
+                } else {
+                    Some(pile_button.0)
+                };
+            }
+        }
+        let close_clicked = q_close_button
+            .iter()
+            .any(|interaction| *interaction == Interaction::Clicked);
+        if viewer.pile.is_some() && (close_clicked || keys.just_pressed(KeyCode::Escape)) {
+            viewer.pile = None;
+        }
+    }
+
+    // Rebuild the pile viewer whenever another pile is opened or the
+    // cards in the open one change
+    pub fn refresh_pile_viewer(
+        mut commands: Commands,
+        viewer: Res<PileViewer>,
+        presentation: Res<Presentation>,
+        q_root: Query<Entity, With<PileViewerRoot>>,
+        font_handles: Res<FontHandles>,
+        image_handles: Res<ImageHandles>,
+        mut shown: Local<Option<(Pile, Vec<u32>)>>,
+    ) {
+        let cards = viewer
+            .pile
+            .map(|pile| (pile, pile.cards(&presentation.shown)));
+        let ids = cards
+            .as_ref()
+            .map(|(pile, cards)| (*pile, cards.iter().map(|card| card.id).collect()));
+        if !viewer.is_added() && *shown == ids {
+            return;
+        }
+        *shown = ids;
+        for root in &q_root {
+            commands.entity(root).despawn_recursive();
+        }
+        let (pile, cards) = match cards {
+            Some(cards) => cards,
+            None => return,
+        };
+        let text_style = TextStyle {
+            font: font_handles.regular.clone(),
+            font_size: 40.0,
+            color: Color::WHITE,
+        };
+        commands
+            .spawn(NodeBundle {
+                style: Style {
+                    position_type: PositionType::Absolute,
+                    size: Size::all(Val::Percent(100.0)),
+                    flex_direction: FlexDirection::Column,
+                    justify_content: JustifyContent::Center,
+                    align_items: AlignItems::Center,
+                    ..default()
+                },
+                background_color: Color::rgba(0.0, 0.0, 0.0, 0.85).into(),
+                // Keep the table underneath from being clicked
+                focus_policy: FocusPolicy::Block,
+                z_index: ZIndex::Global(1),
+                ..default()
+            })
+            .insert((CardGameRoot, PileViewerRoot))
+            .with_children(|root| {
+                root.spawn(TextBundle::from_section(
+                    format!("{} ({})", pile.name(), cards.len()),
+                    text_style.clone(),
+                ));
+                root.spawn(NodeBundle {
+                    style: Style {
+                        size: Size::new(Val::Percent(80.0), Val::Percent(70.0)),
+                        margin: UiRect::vertical(Val::Px(10.0)),
+                        flex_direction: FlexDirection::Column,
+                        overflow: Overflow::Hidden,
+                        ..default()
+                    },
+                    background_color: Color::rgb(0.2, 0.2, 0.2).into(),
+                    ..default()
+                })
+                .with_children(|frame| {
+                    frame
+                        .spawn(NodeBundle {
+                            style: Style {
+                                flex_wrap: FlexWrap::Wrap,
+                                flex_shrink: 0.0,
+                                justify_content: JustifyContent::Center,
+                                align_content: AlignContent::FlexStart,
+                                padding: UiRect::all(Val::Px(5.0)),
+                                ..default()
+                            },
+                            ..default()
+                        })
+                        .insert(PileViewerGrid::default())
+                        .with_children(|grid| {
+                            if cards.is_empty() {
+                                grid.spawn(TextBundle::from_section(
+                                    "No cards",
+                                    text_style.clone(),
+                                ));
+                            }
+                            for card in cards {
+                                grid.spawn(ImageBundle {
+                                    style: Style {
+                                        size: Size::new(Val::Px(128.0), Val::Px(144.0)),
+                                        margin: UiRect::all(Val::Px(5.0)),
+                                        ..default()
+                                    },
+                                    image: UiImage {
+                                        texture: image_handles
+                                            .cards
+                                            .get(&card.kind)
+                                            .unwrap()
+                                            .face_up
+                                            .clone(),
+                                        ..default()
+                                    },
+                                    ..default()
+                                })
+                                .insert((PileViewerCard(card), Interaction::default()));
+                            }
+                        });
+                });
+                spawn_text_button(root, "Close", &font_handles, PileViewerCloseBtn);
+            });
+    }
+
+    pub fn scroll_pile_viewer(
+        mut ev_mouse_wheel: EventReader<MouseWheel>,
+        mut q_grid: Query<(&mut PileViewerGrid, &mut Style, &Parent, &Node)>,
+        q_node: Query<&Node>,
+    ) {
+        for event in ev_mouse_wheel.iter() {
+            for (mut grid, mut style, parent, grid_node) in &mut q_grid {
+                // Stop once the last row is in view
+                let frame_height = q_node.get(parent.get()).unwrap().size().y;
+                let max_scroll = (grid_node.size().y - frame_height).max(0.0);
+                let dy = match event.unit {
+                    MouseScrollUnit::Line => event.y * 40.0,
+                    MouseScrollUnit::Pixel => event.y,
+                };
+                grid.position = (grid.position + dy).clamp(-max_scroll, 0.0);
+                style.position.top = Val::Px(grid.position);
+            }
+        }
+    }
+
+    pub fn refresh_hud(
+        mut q_hud_text: Query<(&mut Text, &HudText), (Without<ResultText>, Without<FeedbackText>)>,
+        mut q_result_text: Query<&mut Text, (With<ResultText>, Without<FeedbackText>)>,
+        presentation: Res<Presentation>,
+    ) {
+        let table = &presentation.shown;
+        for (mut text, hud_text) in &mut q_hud_text {
+            text.sections[0].value = match hud_text {
+                HudText::Resolve => format!(
+                    "Resolve: {}/{}",
+                    table.player_resolve, table.player_max_resolve
+                ),
+                HudText::Defense => format!("Defense: {}", table.player_defense),
+                HudText::Composure => format!(
+                    "Composure: {}/{}",
+                    table.player_composure, table.player_max_composure
+                ),
+            };
+        }
+        let mut result_text = q_result_text.single_mut();
+        result_text.sections[0].value = match table.result {
+            Some(model::CombatResult::Victory) => "Victory!".to_string(),
+            Some(model::CombatResult::Defeat) => "Defeat...".to_string(),
+            None => String::new(),
+        };
+    }
+
+    pub fn refresh_feedback(
+        time: Res<Time>,
+        mut feedback: ResMut<Feedback>,
+        mut q_feedback_text: Query<&mut Text, With<FeedbackText>>,
+    ) {
+        if feedback.remaining > 0.0 {
+            feedback.remaining -= time.delta_seconds();
+            if feedback.remaining <= 0.0 {
+                feedback.message.clear();
+            }
+        }
+        if feedback.is_changed() {
+            for mut text in &mut q_feedback_text {
+                text.sections[0].value = feedback.message.clone();
+            }
+        }
+    }
+
+    // What the player is told the demon will do at the end of the turn
+    fn intent_text(demon: &model::Demon, table: &model::Table) -> String {
+        if demon.stun_time > 0 {
+            return format!("Stunned ({})", demon.stun_time);
+        }
+        match &demon.intent {
+            model::Intent::Attack => format!(
+                "Attack {}",
+                model::behaviour(demon.kind).attack_damage(
+                    demon,
+                    table.player_resolve,
+                    table.player_max_resolve
+                )
+            ),
+            model::Intent::Buff(amount) => format!("Buff +{}", amount),
+            model::Intent::Defend(amount) => format!("Defend {}", amount),
+            model::Intent::Inject(kind) => format!("Inject {}", kind),
+            model::Intent::Afflict(kind) => format!("Afflict {}", kind),
+        }
+    }
+
+    fn plural(count: u32, word: &str) -> String {
+        if count == 1 {
+            format!("{} {}", count, word)
+        } else {
+            format!("{} {}s", count, word)
+        }
+    }
+
+    // What an effect would do if it happened now
+    fn effect_text(effect: &model::CardEffect, table: &model::Table) -> String {
+        match *effect {
+            model::CardEffect::Draw(amount) => format!("Draw {}", plural(amount, "card")),
+            model::CardEffect::DiscardRandom(amount) => {
+                format!("Discard {} at random", plural(amount, "card"))
+            }
+            model::CardEffect::GainDefense(amount) => format!("Gain {} defense", amount),
+            model::CardEffect::Heal(amount) => {
+                // Resolve can't go over the maximum
+                let healed = amount.min(table.player_max_resolve - table.player_resolve);
+                if healed < amount {
+                    format!("Restore {} resolve ({} now)", amount, healed)
+                } else {
+                    format!("Restore {} resolve", amount)
+                }
+            }
+            model::CardEffect::LoseResolve(amount) => format!("Lose {} resolve", amount),
+            model::CardEffect::WeakenDemons(amount) => {
+                format!("Reduce the power of every demon by {}", amount)
+            }
+            model::CardEffect::StunDemons(turns) => {
+                format!("Stun every demon for {}", plural(turns, "turn"))
+            }
+            model::CardEffect::WeakenDemon(amount) => {
+                format!("Reduce the power of a demon by {}", amount)
+            }
+            model::CardEffect::StunDemon(turns) => {
+                format!("Stun a demon for {}", plural(turns, "turn"))
+            }
+            model::CardEffect::DamageDemons(amount) => {
+                format!("Deal {} damage to every demon", amount)
+            }
+            model::CardEffect::DamageDemon(amount) => format!("Deal {} damage to a demon", amount),
+        }
+    }
+
+    fn keyword_text(keyword: model::Keyword) -> &'static str {
+        match keyword {
+            model::Keyword::Exhaust => "Exhaust: removed from the fight once played",
+            model::Keyword::Retain => "Retain: stays in hand at the end of the turn",
+            model::Keyword::Ethereal => {
+                "Ethereal: exhausted if still in hand at the end of the turn"
+            }
+            model::Keyword::Innate => "Innate: always in the opening hand",
+            model::Keyword::Unplayable => "Unplayable: can't be played",
+        }
+    }
+
+    fn phase_text(phase: model::TurnPhase) -> &'static str {
+        match phase {
+            model::TurnPhase::StartOfTurn => "the start of each turn",
+            model::TurnPhase::PlayerMain => "each of your turns",
+            model::TurnPhase::EndOfTurn => "the end of each turn",
+            model::TurnPhase::DemonPhase => "each demon phase",
+            model::TurnPhase::Draw => "each draw",
+        }
+    }
+
+    // Everything a card does, in words
+    fn card_tooltip_text(definition: &model::CardDefinition, table: &model::Table) -> String {
+        let mut lines = Vec::new();
+        if definition.cost > table.player_composure {
+            lines.push(format!(
+                "Cost: {} composure (you have {})",
+                definition.cost, table.player_composure
+            ));
+        } else {
+            lines.push(format!("Cost: {} composure", definition.cost));
+        }
+        for keyword in &definition.keywords {
+            lines.push(keyword_text(*keyword).to_string());
+        }
+        if !definition.effects.is_empty() {
+            lines.push("When played:".to_string());
+            for effect in &definition.effects {
+                lines.push(format!("  {}", effect_text(effect, table)));
+            }
+        }
+        if !definition.while_held.is_empty() {
+            lines.push("If still in hand at the end of the turn:".to_string());
+            for effect in &definition.while_held {
+                lines.push(format!("  {}", effect_text(effect, table)));
+            }
+        }
+        for (phase, effect) in &definition.triggers {
+            lines.push(format!("Once played, at {}:", phase_text(*phase)));
+            lines.push(format!("  {}", effect_text(effect, table)));
+        }
+        lines.join("\n")
+    }
+
+    // Show the tooltip for the card under the mouse, next to it
+    pub fn refresh_card_tooltip(
+        q_window: Query<&Window, With<PrimaryWindow>>,
+        q_cards: Query<(&Interaction, &Card)>,
+        q_viewer_cards: Query<(&Interaction, &PileViewerCard)>,
+        mut q_tooltip: Query<(&mut Style, &mut Visibility), With<CardTooltip>>,
+        mut q_tooltip_text: Query<&mut Text, With<CardTooltipText>>,
+        catalogue: Res<model::CardCatalogue>,
+        presentation: Res<Presentation>,
+    ) {
+        let (mut style, mut visibility) = q_tooltip.single_mut();
+        let hovered = q_cards
+            .iter()
+            .map(|(interaction, card)| (interaction, &card.model))
+            .chain(
+                q_viewer_cards
+                    .iter()
+                    .map(|(interaction, card)| (interaction, &card.0)),
+            )
+            .find(|(interaction, _)| **interaction != Interaction::None)
+            .and_then(|(_, card)| catalogue.get(&card.kind));
+        let window = match q_window.get_single() {
+            Ok(window) => window,
+            Err(_) => return,
+        };
+        let (definition, cursor) = match (hovered, window.cursor_position()) {
+            (Some(definition), Some(cursor)) => (definition, cursor),
+            _ => {
+                *visibility = Visibility::Hidden;
+                return;
+            }
+        };
+        *visibility = Visibility::Visible;
+
+        let mut text = q_tooltip_text.single_mut();
+        // Only touch the text when it changes, so it isn't laid out again
+        // every frame
+        let body = format!("\n{}", card_tooltip_text(definition, &presentation.shown));
+        if text.sections[0].value != definition.name.0 || text.sections[1].value != body {
+            text.sections[0].value = definition.name.0.clone();
+            text.sections[1].value = body;
+        }
+
+        // Keep to the side of the cursor with the most room, the cursor
+        // being measured from the bottom left of the window
+        let left = (cursor.x + 16.0).min(window.width() - TOOLTIP_WIDTH);
+        style.position = if cursor.y < window.height() / 2.0 {
+            UiRect {
+                left: Val::Px(left),
+                bottom: Val::Px(cursor.y + 16.0),
+                ..default()
+            }
+        } else {
+            UiRect {
+                left: Val::Px(left),
+                top: Val::Px(window.height() - cursor.y + 16.0),
+                ..default()
+            }
+        };
+    }
+
+    pub fn refresh_demons(
+        mut commands: Commands,
+        q_demon_area: Query<Entity, With<DemonArea>>,
+        font_handles: Res<FontHandles>,
+        presentation: Res<Presentation>,
+        targeting: Res<Targeting>,
+    ) {
+        if !presentation.is_changed() && !targeting.is_changed() {
+            return;
+        }
+        // Demons light up when they can be chosen as a target
+        let body_color = if targeting.card.is_some() {
+            Color::rgb(0.9, 0.8, 0.3)
+        } else {
+            Color::rgb(0.5, 0.5, 0.5)
+        };
+        let demon_area = q_demon_area.single();
+        commands.entity(demon_area).despawn_descendants();
+        commands.entity(demon_area).with_children(|parent| {
+            for (index, demon) in presentation.shown.demons.iter().enumerate() {
+                let text_style = TextStyle {
+                    font: font_handles.regular.clone(),
+                    font_size: 24.0,
+                    color: Color::BLACK,
+                };
+                parent
+                    .spawn(NodeBundle {
+                        style: Style {
+                            flex_direction: FlexDirection::Column,
+                            align_items: AlignItems::Center,
+                            ..default()
+                        },
+                        ..default()
+                    })
+                    .with_children(|column| {
+                        // Intent above the demon
+                        column.spawn(TextBundle::from_section(
+                            intent_text(demon, &presentation.shown),
+                            TextStyle {
+                                color: Color::rgb(0.7, 0.0, 0.0),
+                                ..text_style.clone()
+                            },
+                        ));
+                        column
+                            .spawn(ButtonBundle {
+                                style: Style {
+                                    size: Size::new(Val::Px(160.0), Val::Px(200.0)),
+                                    flex_direction: FlexDirection::Column,
+                                    justify_content: JustifyContent::Center,
+                                    align_items: AlignItems::Center,
+                                    margin: UiRect {
+                                        top: Val::Px(5.0),
+                                        ..default()
+                                    },
+                                    ..default()
+                                },
+                                background_color: body_color.into(),
+                                ..default()
+                            })
+                            .insert(DemonButton(index))
+                            .with_children(|body| {
+                                body.spawn(TextBundle::from_section(
+                                    format!("{:?}", demon.kind),
+                                    text_style.clone(),
+                                ));
+                                body.spawn(TextBundle::from_section(
+                                    format!("Health: {}/{}", demon.health, demon.max_health),
+                                    text_style.clone(),
+                                ));
+                                body.spawn(TextBundle::from_section(
+                                    format!("Power: {}", demon.power),
+                                    text_style.clone(),
+                                ));
+                                body.spawn(
+                                    TextBundle::from_section(
+                                        model::behaviour(demon.kind).description(),
+                                        TextStyle {
+                                            font_size: 16.0,
+                                            ..text_style.clone()
+                                        },
+                                    )
+                                    .with_style(Style {
+                                        max_size: Size::width(Val::Px(150.0)),
+                                        ..default()
+                                    }),
+                                );
+                                if demon.defense > 0 {
+                                    body.spawn(TextBundle::from_section(
+                                        format!("Defense: {}", demon.defense),
+                                        text_style.clone(),
+                                    ));
+                                }
+                            });
+                    });
+            }
+        });
+    }
+
     pub fn hand_card_interaction(
         mut q_interaction: Query<
             (Entity, &Interaction, &mut UiImage, &'static Card),
             (Changed<Interaction>, With<Button>),
         >,
         mut game_model: ResMut<model::CardGameModel>,
+        mut targeting: ResMut<Targeting>,
+        catalogue: Res<model::CardCatalogue>,
+        presentation: Res<Presentation>,
+        mut feedback: ResMut<Feedback>,
+        settings: Res<Settings>,
     ) {
         for (e_card, interaction, mut image, card) in &mut q_interaction {
             match *interaction {
                 Interaction::Clicked => {
-                    game_model.play(card.model.id);
+                    // Wait until the player can see what they're playing
+                    if !presentation.is_idle() {
+                        continue;
+                    }
+                    // Cards already in play can't be played again
+                    if !presentation
+                        .shown
+                        .hand
+                        .iter()
+                        .any(|hand_card| hand_card.id == card.model.id)
+                    {
+                        continue;
+                    }
+                    let needs_target = catalogue
+                        .get(&card.model.kind)
+                        .is_some_and(|definition| definition.needs_target());
+                    if needs_target {
+                        // Say straight away if the card can't be played at
+                        // all, rather than after a demon has been picked.
+                        // There's always a first demon while the fight is on
+                        targeting.card = None;
+                        if let Err(err) = game_model.check_play(card.model.id, Some(0)) {
+                            feedback.show(
+                                format!("Can't play {}: {}", card.model.kind, err),
+                                &settings.presentation,
+                            );
+                            continue;
+                        }
+                        // Play the card once the player has picked a demon
+                        targeting.card = Some(card.model.clone());
+                    } else {
+                        targeting.card = None;
+                        if let Err(err) = game_model.play(card.model.id, None) {
+                            feedback.show(
+                                format!("Can't play {}: {}", card.model.kind, err),
+                                &settings.presentation,
+                            );
+                        }
+                    }
                 }
                 Interaction::Hovered => image.texture = card.image_handles.hover.clone(),
                 Interaction::None => image.texture = card.image_handles.face_up.clone(),
@@ -460,6 +1792,195 @@ mod view {
         }
     }
 
+    pub fn demon_interaction(
+        q_interaction: Query<(&Interaction, &DemonButton), Changed<Interaction>>,
+        mut game_model: ResMut<model::CardGameModel>,
+        mut targeting: ResMut<Targeting>,
+        mut feedback: ResMut<Feedback>,
+        settings: Res<Settings>,
+    ) {
+        for (interaction, demon_button) in &q_interaction {
+            if *interaction != Interaction::Clicked {
+                continue;
+            }
+            if let Some(card) = targeting.card.take() {
+                if let Err(err) = game_model.play(card.id, Some(demon_button.0)) {
+                    feedback.show(
+                        format!("Can't play {}: {}", card.kind, err),
+                        &settings.presentation,
+                    );
+                }
+            }
+        }
+    }
+
+    pub fn cancel_targeting(
+        keys: Res<Input<KeyCode>>,
+        mouse_buttons: Res<Input<MouseButton>>,
+        mut targeting: ResMut<Targeting>,
+    ) {
+        if targeting.card.is_some()
+            && (keys.just_pressed(KeyCode::Escape)
+                || mouse_buttons.just_pressed(MouseButton::Right))
+        {
+            targeting.card = None;
+        }
+    }
+
+    // Take back the last card played, from the HUD button or the Z key
+    pub fn undo_interaction(
+        q_interaction: Query<&Interaction, (Changed<Interaction>, With<UndoBtn>)>,
+        keys: Res<Input<KeyCode>>,
+        mut game_model: ResMut<model::CardGameModel>,
+        presentation: Res<Presentation>,
+        mut targeting: ResMut<Targeting>,
+        mut feedback: ResMut<Feedback>,
+        settings: Res<Settings>,
+    ) {
+        let clicked = q_interaction
+            .iter()
+            .any(|interaction| *interaction == Interaction::Clicked);
+        if !(clicked || keys.just_pressed(KeyCode::Z)) || !presentation.is_idle() {
+            return;
+        }
+        targeting.card = None;
+        if let Err(err) = game_model.undo() {
+            feedback.show(format!("Can't undo: {}", err), &settings.presentation);
+        }
+    }
+
+    // Turn autoplay on and off, from the HUD button or the A key
+    pub fn autoplay_interaction(
+        q_interaction: Query<&Interaction, (Changed<Interaction>, With<AutoplayBtn>)>,
+        mut q_autoplay_btn: Query<&mut BackgroundColor, With<AutoplayBtn>>,
+        keys: Res<Input<KeyCode>>,
+        mut autoplay: ResMut<Autoplay>,
+    ) {
+        let clicked = q_interaction
+            .iter()
+            .any(|interaction| *interaction == Interaction::Clicked);
+        if clicked || keys.just_pressed(KeyCode::A) {
+            autoplay.enabled = !autoplay.enabled;
+        }
+        for mut color in &mut q_autoplay_btn {
+            *color = if autoplay.enabled {
+                TEXT_BUTTON_ACTIVE_COLOR.into()
+            } else {
+                TEXT_BUTTON_COLOR.into()
+            };
+        }
+    }
+
+    pub fn autoplay(
+        time: Res<Time>,
+        settings: Res<Settings>,
+        mut autoplay: ResMut<Autoplay>,
+        mut game_model: ResMut<model::CardGameModel>,
+        presentation: Res<Presentation>,
+        mut targeting: ResMut<Targeting>,
+        mut feedback: ResMut<Feedback>,
+    ) {
+        // Give the player a moment to see each move
+        if !autoplay.enabled || !presentation.is_idle() || game_model.result().is_some() {
+            autoplay.wait = settings.presentation.autoplay_delay;
+            return;
+        }
+        autoplay.wait -= time.delta_seconds();
+        if autoplay.wait > 0.0 {
+            return;
+        }
+        autoplay.wait = settings.presentation.autoplay_delay;
+        targeting.card = None;
+        let action = autoplay.strategy.choose_action(&game_model);
+        if let Err(err) = game_model.apply(&action) {
+            autoplay.enabled = false;
+            feedback.show(
+                format!("Autoplay stopped, {:?} failed: {}", action, err),
+                &settings.presentation,
+            );
+        }
+    }
+
+    // Apply the replay's actions one at a time, paced like autoplay, and
+    // hand the fight over to the player once there are none left
+    pub fn play_back_replay(
+        mut commands: Commands,
+        time: Res<Time>,
+        settings: Res<Settings>,
+        mut playback: ResMut<model::ReplayPlayback>,
+        mut game_model: ResMut<model::CardGameModel>,
+        presentation: Res<Presentation>,
+        mut feedback: ResMut<Feedback>,
+    ) {
+        if !presentation.is_idle() || game_model.result().is_some() {
+            playback.wait = settings.presentation.autoplay_delay;
+            return;
+        }
+        playback.wait -= time.delta_seconds();
+        if playback.wait > 0.0 {
+            return;
+        }
+        playback.wait = settings.presentation.autoplay_delay;
+        let action = match playback.actions.pop_front() {
+            Some(action) => action,
+            None => {
+                commands.remove_resource::<model::ReplayPlayback>();
+                feedback.show("End of replay".to_string(), &settings.presentation);
+                return;
+            }
+        };
+        if let Err(err) = game_model.apply(&action) {
+            // The game has gone a different way than when it was
+            // recorded, so the rest of the replay means nothing
+            commands.remove_resource::<model::ReplayPlayback>();
+            feedback.show(
+                format!("Replay stopped, {:?} failed: {}", action, err),
+                &settings.presentation,
+            );
+        }
+    }
+
+    // Keep the game going between fights while autoplay is on, so it can
+    // be left running unattended
+    pub fn autoplay_between_fights(
+        time: Res<Time>,
+        settings: Res<Settings>,
+        state: Res<State<AppState>>,
+        mut autoplay: ResMut<Autoplay>,
+        offer: Option<Res<RewardOffer>>,
+        run_deck: Option<ResMut<model::RunDeck>>,
+        mut next_state: ResMut<NextState<AppState>>,
+    ) {
+        if matches!(state.0, AppState::Loading | AppState::CardCombat) {
+            return;
+        }
+        // Leave each screen up for long enough to be seen
+        if !autoplay.enabled || state.is_changed() {
+            autoplay.wait = settings.presentation.autoplay_screen_delay;
+            return;
+        }
+        autoplay.wait -= time.delta_seconds();
+        if autoplay.wait > 0.0 {
+            return;
+        }
+        autoplay.wait = settings.presentation.autoplay_screen_delay;
+        match state.0 {
+            AppState::MainMenu => next_state.set(AppState::Overworld),
+            AppState::Overworld => next_state.set(AppState::CardCombat),
+            AppState::Reward => {
+                // Take whatever is offered first, the offer being random
+                let choice = offer.and_then(|offer| offer.choices.first().cloned());
+                if let (Some(kind), Some(mut run_deck)) = (choice, run_deck) {
+                    info!("Autoplay added {} to the deck", kind);
+                    run_deck.add(kind);
+                }
+                next_state.set(AppState::Overworld);
+            }
+            AppState::GameOver => next_state.set(AppState::MainMenu),
+            AppState::Loading | AppState::CardCombat => {}
+        }
+    }
+
     pub fn end_turn_btn_interaction(
         mut q_interaction: Query<
             (&Interaction, &mut UiImage),
@@ -467,11 +1988,23 @@ mod view {
         >,
         mut game_model: ResMut<model::CardGameModel>,
         image_handles: Res<ImageHandles>,
+        presentation: Res<Presentation>,
+        mut targeting: ResMut<Targeting>,
+        mut feedback: ResMut<Feedback>,
+        settings: Res<Settings>,
     ) {
         for (interaction, mut image) in &mut q_interaction {
             match *interaction {
                 Interaction::Clicked => {
-                    game_model.end_turn();
+                    if presentation.is_idle() {
+                        targeting.card = None;
+                        if let Err(err) = game_model.end_turn() {
+                            feedback.show(
+                                format!("Can't end the turn: {}", err),
+                                &settings.presentation,
+                            );
+                        }
+                    }
                 }
                 Interaction::Hovered => image.texture = image_handles.end_turn_btn.hover.clone(),
                 Interaction::None => image.texture = image_handles.end_turn_btn.normal.clone(),
@@ -485,185 +2018,1645 @@ mod view {
 // but this model stores the actual state of the game and performs
 // mutations on it.
 
-// The model and the view will communicate via bevy events, which
-// will be queued up by the model and then displayed at a sensible
-// speed for the player.
+// The model and the view communicate via bevy events, which are
+// queued up by the model and then displayed at a sensible speed
+// for the player.
 
-mod model {
+pub mod model {
+
+    use std::collections::VecDeque;
+    use std::fmt;
+    use std::fs;
+    use std::path::Path;
+    use std::sync::Arc;
 
     use bevy::prelude::*;
     use rand::seq::SliceRandom;
-    use rand::thread_rng;
+    use rand::{thread_rng, Rng, RngCore, SeedableRng};
+    use rand_chacha::ChaCha8Rng;
+    use serde::{Deserialize, Serialize};
 
+    use super::{CardGameEvent, CardGameUpdate};
     use crate::settings::Settings;
 
-    #[derive(Resource)]
+    // Where the card definitions live, relative to the working directory
+    pub const CARD_CATALOGUE_PATH: &str = "assets/cards.ron";
+
+    // The demons every fight is against, for now
+    pub const STARTING_DEMONS: [DemonKind; 3] =
+        [DemonKind::Fear, DemonKind::Despair, DemonKind::Doubt];
+
+    // Cards the game itself refers to by name, which the catalogue has
+    // to define however else it's changed
+    const TIRED: &str = "Tired";
+    const DIZZY: &str = "Dizzy";
+    const STRESSED: &str = "Stressed";
+    const REQUIRED_CARDS: [&str; 3] = [TIRED, DIZZY, STRESSED];
+
+    // Everything but the catalogue and history is saved, see `crate::save`
+    #[derive(Resource, Clone, Serialize, Deserialize)]
     pub struct CardGameModel {
         pub demons: Vec<Demon>,
         pub player_resolve: u32,
+        pub player_max_resolve: u32,
         pub player_defense: u32,
+        // Percentage of defense that carries over into the next turn
+        defense_retained_percent: u32,
+        // Spent to play cards, and restored at the start of each turn
+        pub player_composure: u32,
+        pub player_max_composure: u32,
+        // Cards drawn at the end of each turn
+        hand_size: usize,
+        // Draws beyond this many cards in hand fail
+        max_hand_size: usize,
         pub deck: Vec<Card>,
         pub discard_pile: Vec<Card>,
         pub hand: Vec<Card>,
         pub in_play: Vec<Card>,
+        // Cards that are out of the fight for good
+        pub exhaust_pile: Vec<Card>,
+        pub phase: TurnPhase,
+        // Effects waiting for a phase to come round, other than the
+        // ones demons bring with them
+        triggers: Vec<Trigger>,
         next_card_id: u32,
+        #[serde(skip)]
+        catalogue: CardCatalogue,
+        // All randomness in the game comes from here, so a game can be
+        // replayed exactly from its seed
+        pub seed: u64,
+        rng: ChaCha8Rng,
+        // How the fight ended, once it has
+        result: Option<CombatResult>,
+        // Events that have happened since they were last sent out
+        #[serde(skip)]
+        updates: Vec<CardGameUpdate>,
+        // The cards played this turn, along with the game as it was
+        // before each one, for as long as they can be taken back
+        #[serde(skip)]
+        undo_stack: Vec<UndoEntry>,
+        // Set when the player learns something they couldn't take back,
+        // like which card was on top of the deck
+        #[serde(skip)]
+        revealed: bool,
+        // The game as it was when recording started, and everything the
+        // player has done since, see `recording`
+        #[serde(skip)]
+        recording_start: Option<Box<CardGameModel>>,
+        #[serde(skip)]
+        recorded_actions: Vec<PlayerAction>,
+    }
+
+    // Something the player chose to do
+    #[derive(Clone, Debug, Serialize, Deserialize)]
+    pub enum PlayerAction {
+        Play { card_id: u32, target: Option<usize> },
+        EndTurn,
+        Undo,
+    }
+
+    #[derive(Clone)]
+    struct UndoEntry {
+        action: PlayerAction,
+        before: Box<CardGameModel>,
+    }
+
+    // What the player can see of the game at one point in time
+    #[derive(Clone, Debug, Default)]
+    pub struct Table {
+        pub demons: Vec<Demon>,
+        pub player_resolve: u32,
+        pub player_max_resolve: u32,
+        pub player_defense: u32,
+        pub player_composure: u32,
+        pub player_max_composure: u32,
+        pub deck: Vec<Card>,
+        pub discard_pile: Vec<Card>,
+        pub hand: Vec<Card>,
+        pub in_play: Vec<Card>,
+        pub exhaust_pile: Vec<Card>,
+        pub phase: TurnPhase,
+        pub result: Option<CombatResult>,
     }
 
     impl CardGameModel {
         pub fn new(
             demons: Vec<DemonKind>,
             starter_cards: Vec<CardKind>,
+            catalogue: &CardCatalogue,
             settings: &Settings,
+            seed: u64,
         ) -> Self {
             let mut card_game_model = CardGameModel {
                 demons: demons
                     .iter()
                     .map(|kind| Demon {
                         kind: *kind,
+                        health: settings.game.inner.starting_demon_health,
+                        max_health: settings.game.inner.starting_demon_health,
                         power: settings.game.inner.starting_demon_power,
                         stun_time: settings.game.inner.starting_demon_stun_time,
+                        defense: 0,
+                        intent: Intent::Attack,
                     })
                     .collect(),
                 player_resolve: settings.game.inner.starting_resolve,
+                player_max_resolve: settings.game.inner.starting_resolve,
                 player_defense: 0,
+                defense_retained_percent: settings.game.inner.defense_retained_percent,
+                player_composure: 0,
+                player_max_composure: settings.game.inner.composure_per_turn,
+                hand_size: settings.game.inner.hand_size,
+                max_hand_size: settings.game.inner.max_hand_size,
                 deck: starter_cards
                     .iter()
                     .zip((0..))
-                    .map(|(kind, id)| Card { id, kind: *kind })
+                    .map(|(kind, id)| Card {
+                        id,
+                        kind: kind.clone(),
+                    })
                     .collect(),
                 discard_pile: Vec::new(),
                 hand: Vec::new(),
                 in_play: Vec::new(),
+                exhaust_pile: Vec::new(),
+                phase: TurnPhase::StartOfTurn,
+                triggers: Vec::new(),
                 next_card_id: starter_cards.len() as u32,
+                catalogue: catalogue.clone(),
+                seed,
+                rng: ChaCha8Rng::seed_from_u64(seed),
+                result: None,
+                updates: Vec::new(),
+                undo_stack: Vec::new(),
+                revealed: false,
+                recording_start: None,
+                recorded_actions: Vec::new(),
             };
-            card_game_model.deck.shuffle(&mut thread_rng());
-            for _ in 0..5 {
-                card_game_model.draw();
+            card_game_model.deck.shuffle(&mut card_game_model.rng);
+            // Innate cards go on top of the deck so they start in hand
+            let deck = std::mem::take(&mut card_game_model.deck);
+            let (mut innate, mut others): (Vec<Card>, Vec<Card>) = deck
+                .into_iter()
+                .partition(|card| card_game_model.has_keyword(card, Keyword::Innate));
+            let opening_hand_size = innate.len().max(card_game_model.hand_size);
+            card_game_model.deck.append(&mut others);
+            card_game_model.deck.append(&mut innate);
+            for _ in 0..opening_hand_size {
+                if card_game_model.draw().is_err() {
+                    break;
+                }
             }
+            card_game_model.start_turn();
+            card_game_model.start_recording();
             card_game_model
         }
 
-        pub fn start_turn(&mut self) {}
+        fn enter_phase(&mut self, phase: TurnPhase) {
+            self.phase = phase;
+            self.record(CardGameEvent::EnterPhase(phase));
+        }
 
-        pub fn draw(&mut self) {
-            // If there are no cards to draw, shuffle discard pile into deck
-            if self.deck.is_empty() {
-                self.deck.append(&mut self.discard_pile);
-                self.deck.shuffle(&mut thread_rng());
-            }
-            let card = self.deck.pop().unwrap();
-            self.hand.push(card);
+        // Add an effect to apply every time the phase comes round
+        fn add_trigger(&mut self, trigger: Trigger) {
+            self.triggers.push(trigger);
         }
 
-        fn find_card_in_hand(&self, card_id: u32) -> usize {
-            // Find the index of the card with the given card_id
-            self.hand
+        // Apply everything hooked into a phase: registered triggers
+        // first, then whatever the demons that can act bring with them
+        fn run_triggers(&mut self, phase: TurnPhase) {
+            let mut effects: Vec<(CardEffect, Option<usize>)> = self
+                .triggers
                 .iter()
-                .zip((0..))
-                .find(|(c, i)| c.id == card_id)
-                .unwrap()
-                .1
+                .filter(|trigger| trigger.phase == phase)
+                .map(|trigger| (trigger.effect, None))
+                .collect();
+            for (index, demon) in self.demons.iter().enumerate() {
+                if demon.stun_time > 0 {
+                    continue;
+                }
+                for (trigger_phase, effect) in behaviour(demon.kind).triggers(demon) {
+                    if trigger_phase == phase {
+                        effects.push((effect, Some(index)));
+                    }
+                }
+            }
+            for (effect, target) in effects {
+                self.apply_effect(effect, target);
+            }
         }
 
-        pub fn discard(&mut self, card_id: u32) {
-            let card_index = self.find_card_in_hand(card_id);
-            let card = self.hand.remove(card_index);
-            self.discard_pile.push(card);
+        fn start_turn(&mut self) {
+            self.enter_phase(TurnPhase::StartOfTurn);
+            let retained = self.player_defense * self.defense_retained_percent / 100;
+            if retained < self.player_defense {
+                let lost = self.player_defense - retained;
+                self.player_defense = retained;
+                self.record(CardGameEvent::LoseDefense(lost));
+            }
+            let restored = self.player_max_composure - self.player_composure;
+            self.player_composure = self.player_max_composure;
+            self.record(CardGameEvent::RestoreComposure(restored));
+            // Demons show their hand before the player plays theirs
+            for index in 0..self.demons.len() {
+                let demon = &self.demons[index];
+                let intent = behaviour(demon.kind).choose_intent(demon, &mut self.rng);
+                self.demons[index].intent = intent.clone();
+                self.record(CardGameEvent::ChooseIntent(index, intent));
+            }
+            self.run_triggers(TurnPhase::StartOfTurn);
+            self.check_result();
+            if self.result.is_some() {
+                return;
+            }
+            self.enter_phase(TurnPhase::PlayerMain);
+            self.run_triggers(TurnPhase::PlayerMain);
+            self.check_result();
         }
 
-        pub fn gain(&mut self, kind: CardKind) {
-            let card = Card {
+        pub fn draw(&mut self) -> Result<(), CardGameError> {
+            let failure = if self.hand.len() >= self.max_hand_size {
+                Some(DrawError::HandFull)
+            } else if self.deck.is_empty() && self.discard_pile.is_empty() {
+                Some(DrawError::NothingToDraw)
+            } else {
+                None
+            };
+            if let Some(err) = failure {
+                self.record(CardGameEvent::DrawFailed(err));
+                return Err(CardGameError::CannotDraw(err));
+            }
+            // If there are no cards to draw, shuffle discard pile into deck
+            if self.deck.is_empty() {
+                self.deck.append(&mut self.discard_pile);
+                self.deck.shuffle(&mut self.rng);
+                self.record(CardGameEvent::ShuffleDiscardToDeck);
+            }
+            let card = self.deck.pop().expect("Deck is empty after reshuffling");
+            self.revealed = true;
+            self.hand.push(card.clone());
+            self.record(CardGameEvent::DrawCard(card));
+            Ok(())
+        }
+
+        pub fn table(&self) -> Table {
+            Table {
+                demons: self.demons.clone(),
+                player_resolve: self.player_resolve,
+                player_max_resolve: self.player_max_resolve,
+                player_defense: self.player_defense,
+                player_composure: self.player_composure,
+                player_max_composure: self.player_max_composure,
+                deck: self.deck.clone(),
+                discard_pile: self.discard_pile.clone(),
+                hand: self.hand.clone(),
+                in_play: self.in_play.clone(),
+                exhaust_pile: self.exhaust_pile.clone(),
+                phase: self.phase,
+                result: self.result,
+            }
+        }
+
+        // Note that an event has happened, along with how the table looks now
+        fn record(&mut self, event: CardGameEvent) {
+            let table = self.table();
+            self.updates.push(CardGameUpdate { event, table });
+        }
+
+        // Take the updates that have happened since the last call
+        pub fn drain_updates(&mut self) -> std::vec::Drain<'_, CardGameUpdate> {
+            self.updates.drain(..)
+        }
+
+        fn find_card_in_hand(&self, card_id: u32) -> Result<usize, CardGameError> {
+            // Find the index of the card with the given card_id
+            self.hand
+                .iter()
+                .position(|card| card.id == card_id)
+                .ok_or(CardGameError::CardNotInHand(card_id))
+        }
+
+        fn definition(&self, kind: &CardKind) -> Result<&CardDefinition, CardGameError> {
+            self.catalogue
+                .get(kind)
+                .ok_or_else(|| CardGameError::UnknownCard(kind.clone()))
+        }
+
+        pub fn discard(&mut self, card_id: u32) -> Result<(), CardGameError> {
+            if self.result.is_some() {
+                return Err(CardGameError::GameOver);
+            }
+            let card_index = self.find_card_in_hand(card_id)?;
+            let card = self.hand.remove(card_index);
+            self.discard_pile.push(card.clone());
+            self.record(CardGameEvent::DiscardCard(card));
+            Ok(())
+        }
+
+        // Put a new card somewhere in the deck without revealing where
+        fn shuffle_into_deck(&mut self, kind: CardKind) -> Result<(), CardGameError> {
+            self.definition(&kind)?;
+            let card = Card {
+                id: self.next_card_id,
+                kind,
+            };
+            let position = self.rng.gen_range(0..=self.deck.len());
+            self.deck.insert(position, card.clone());
+            self.next_card_id += 1;
+            self.record(CardGameEvent::ShuffleCardIntoDeck(card));
+            Ok(())
+        }
+
+        pub fn gain(&mut self, kind: CardKind) -> Result<(), CardGameError> {
+            if self.result.is_some() {
+                return Err(CardGameError::GameOver);
+            }
+            self.definition(&kind)?;
+            let card = Card {
                 id: self.next_card_id,
                 kind,
             };
-            self.discard_pile.push(card);
+            self.discard_pile.push(card.clone());
             self.next_card_id += 1;
+            self.record(CardGameEvent::GainCard(card));
+            Ok(())
+        }
+
+        pub fn apply(&mut self, action: &PlayerAction) -> Result<(), CardGameError> {
+            match *action {
+                PlayerAction::Play { card_id, target } => self.play(card_id, target),
+                PlayerAction::EndTurn => self.end_turn(),
+                PlayerAction::Undo => self.undo().map(|_| ()),
+            }
+        }
+
+        // Whether `play` would accept the card and target, without
+        // playing it
+        pub fn check_play(&self, card_id: u32, target: Option<usize>) -> Result<(), CardGameError> {
+            if self.result.is_some() {
+                return Err(CardGameError::GameOver);
+            }
+            if self.phase != TurnPhase::PlayerMain {
+                return Err(CardGameError::WrongPhase(self.phase));
+            }
+            let card_index = self.find_card_in_hand(card_id)?;
+            let definition = self.definition(&self.hand[card_index].kind)?;
+            if definition.keywords.contains(&Keyword::Unplayable) {
+                return Err(CardGameError::Unplayable);
+            }
+            if definition.cost > self.player_composure {
+                return Err(CardGameError::NotEnoughComposure {
+                    cost: definition.cost,
+                    available: self.player_composure,
+                });
+            }
+            match target {
+                Some(index) if definition.needs_target() && index < self.demons.len() => {}
+                None if !definition.needs_target() => {}
+                _ => return Err(CardGameError::InvalidTarget),
+            }
+            Ok(())
         }
 
-        pub fn play(&mut self, card_id: u32) {
-            let card_index = self.find_card_in_hand(card_id);
+        // Play a card from the hand. Cards that affect a single demon
+        // need the index of the demon as a target, and others need none.
+        pub fn play(&mut self, card_id: u32, target: Option<usize>) -> Result<(), CardGameError> {
+            self.check_play(card_id, target)?;
+            let card_index = self.find_card_in_hand(card_id)?;
+            let definition = self.definition(&self.hand[card_index].kind)?.clone();
+            self.recorded_actions
+                .push(PlayerAction::Play { card_id, target });
+            let before = self.snapshot();
+            let rng_position = self.rng.get_word_pos();
+            self.revealed = false;
             let card = self.hand.remove(card_index);
-            self.in_play.push(card);
-            // TODO: implement effects of cards
+            self.in_play.push(card.clone());
+            self.record(CardGameEvent::PlayCard(card.clone()));
+            if definition.cost > 0 {
+                self.player_composure -= definition.cost;
+                self.record(CardGameEvent::SpendComposure(definition.cost));
+            }
+            for effect in definition.effects {
+                self.apply_effect(effect, target);
+            }
+            for (phase, effect) in definition.triggers {
+                self.add_trigger(Trigger {
+                    phase,
+                    effect,
+                    source: card.kind.clone(),
+                });
+            }
+            // Only clear away demons once the card has finished with
+            // them, so targets stay valid for all of its effects
+            self.check_result();
+            // Anything random or newly seen can't be taken back, and
+            // neither can anything played before it
+            if self.revealed || self.rng.get_word_pos() != rng_position {
+                self.undo_stack.clear();
+            } else {
+                self.undo_stack.push(UndoEntry {
+                    action: PlayerAction::Play { card_id, target },
+                    before: Box::new(before),
+                });
+            }
+            Ok(())
+        }
+
+        // Pick up a fight that was saved, which doesn't keep the
+        // catalogue or anything that had happened before it was saved
+        pub fn resume(&mut self, catalogue: &CardCatalogue) -> Result<(), CardGameError> {
+            self.catalogue = catalogue.clone();
+            for card in self
+                .deck
+                .iter()
+                .chain(&self.discard_pile)
+                .chain(&self.hand)
+                .chain(&self.in_play)
+                .chain(&self.exhaust_pile)
+            {
+                self.definition(&card.kind)?;
+            }
+            self.updates.clear();
+            self.undo_stack.clear();
+            self.start_recording();
+            self.record(CardGameEvent::Resume);
+            Ok(())
+        }
+
+        // Forget what was recorded so far and record from here on
+        fn start_recording(&mut self) {
+            self.recorded_actions.clear();
+            self.recording_start = Some(Box::new(self.snapshot()));
+        }
+
+        // Where the recording started and the actions since, which
+        // played back in order reproduce the game as it is now
+        pub fn recording(&self) -> Option<(&CardGameModel, &[PlayerAction])> {
+            self.recording_start
+                .as_deref()
+                .map(|start| (start, self.recorded_actions.as_slice()))
+        }
+
+        // The game as it is, without its history. Built field by field
+        // so the history isn't copied only to be thrown away
+        fn snapshot(&self) -> CardGameModel {
+            CardGameModel {
+                demons: self.demons.clone(),
+                player_resolve: self.player_resolve,
+                player_max_resolve: self.player_max_resolve,
+                player_defense: self.player_defense,
+                defense_retained_percent: self.defense_retained_percent,
+                player_composure: self.player_composure,
+                player_max_composure: self.player_max_composure,
+                hand_size: self.hand_size,
+                max_hand_size: self.max_hand_size,
+                deck: self.deck.clone(),
+                discard_pile: self.discard_pile.clone(),
+                hand: self.hand.clone(),
+                in_play: self.in_play.clone(),
+                exhaust_pile: self.exhaust_pile.clone(),
+                phase: self.phase,
+                triggers: self.triggers.clone(),
+                next_card_id: self.next_card_id,
+                catalogue: self.catalogue.clone(),
+                seed: self.seed,
+                rng: self.rng.clone(),
+                result: self.result,
+                updates: Vec::new(),
+                undo_stack: Vec::new(),
+                revealed: self.revealed,
+                recording_start: None,
+                recorded_actions: Vec::new(),
+            }
+        }
+
+        // A copy of the game to try moves out on, that knows no more
+        // than the player does. Its rng is seeded afresh and its deck
+        // shuffled, so nothing random goes the way it will for real
+        pub fn hypothetical(&self, seed: u64) -> CardGameModel {
+            let mut copy = self.snapshot();
+            copy.rng = ChaCha8Rng::seed_from_u64(seed);
+            copy.deck.shuffle(&mut copy.rng);
+            copy
+        }
+
+        // Take back the last card played this turn
+        pub fn undo(&mut self) -> Result<PlayerAction, CardGameError> {
+            if self.result.is_some() {
+                return Err(CardGameError::GameOver);
+            }
+            let entry = self.undo_stack.pop().ok_or(CardGameError::NothingToUndo)?;
+            let undo_stack = std::mem::take(&mut self.undo_stack);
+            let updates = std::mem::take(&mut self.updates);
+            let recording_start = self.recording_start.take();
+            let mut recorded_actions = std::mem::take(&mut self.recorded_actions);
+            *self = *entry.before;
+            self.undo_stack = undo_stack;
+            self.updates = updates;
+            self.recording_start = recording_start;
+            recorded_actions.push(PlayerAction::Undo);
+            self.recorded_actions = recorded_actions;
+            self.record(CardGameEvent::Undo);
+            Ok(entry.action)
+        }
+
+        fn remove_defeated_demons(&mut self) {
+            for index in (0..self.demons.len()).rev() {
+                if self.demons[index].health == 0 {
+                    self.demons.remove(index);
+                    self.record(CardGameEvent::DefeatDemon(index));
+                }
+            }
+        }
+
+        // Clear away defeated demons, then end the fight if either side
+        // has nothing left
+        fn check_result(&mut self) {
+            self.remove_defeated_demons();
+            if self.result.is_some() {
+                return;
+            }
+            let result = if self.player_resolve == 0 {
+                CombatResult::Defeat
+            } else if self.demons.is_empty() {
+                CombatResult::Victory
+            } else {
+                return;
+            };
+            self.result = Some(result);
+            self.record(CardGameEvent::CombatOver(result));
+        }
+
+        pub fn result(&self) -> Option<CombatResult> {
+            self.result
+        }
+
+        pub fn catalogue(&self) -> &CardCatalogue {
+            &self.catalogue
+        }
+
+        // Pick distinct cards to offer the player after a victory, with
+        // rarer cards less likely to come up
+        pub fn reward_choices(&mut self, count: usize) -> Vec<CardKind> {
+            let definitions: Vec<&CardDefinition> = self
+                .catalogue
+                .iter()
+                .filter(|definition| definition.rarity.reward_weight() > 0)
+                .collect();
+            let count = count.min(definitions.len());
+            definitions
+                .choose_multiple_weighted(&mut self.rng, count, |definition| {
+                    definition.rarity.reward_weight()
+                })
+                .map(|chosen| chosen.map(|definition| definition.name.clone()).collect())
+                .unwrap_or_default()
+        }
+
+        fn apply_effect(&mut self, effect: CardEffect, target: Option<usize>) {
+            match effect {
+                CardEffect::Draw(amount) => {
+                    for _ in 0..amount {
+                        if self.draw().is_err() {
+                            break;
+                        }
+                    }
+                }
+                CardEffect::DiscardRandom(amount) => {
+                    for _ in 0..amount {
+                        if self.hand.is_empty() {
+                            break;
+                        }
+                        let card_index = self.rng.gen_range(0..self.hand.len());
+                        let card = self.hand.remove(card_index);
+                        self.discard_pile.push(card.clone());
+                        self.record(CardGameEvent::DiscardCard(card));
+                    }
+                }
+                CardEffect::GainDefense(amount) => {
+                    self.player_defense += amount;
+                    self.record(CardGameEvent::GainDefense(amount));
+                }
+                CardEffect::Heal(amount) => {
+                    let healed = amount.min(self.player_max_resolve - self.player_resolve);
+                    self.player_resolve += healed;
+                    self.record(CardGameEvent::Heal(healed));
+                }
+                CardEffect::LoseResolve(amount) => {
+                    let damage = amount.min(self.player_resolve);
+                    self.player_resolve -= damage;
+                    self.record(CardGameEvent::DamageResolve(damage));
+                }
+                CardEffect::WeakenDemons(amount) => {
+                    for index in 0..self.demons.len() {
+                        self.weaken_demon(index, amount);
+                    }
+                }
+                CardEffect::StunDemons(turns) => {
+                    for index in 0..self.demons.len() {
+                        self.stun_demon(index, turns);
+                    }
+                }
+                CardEffect::WeakenDemon(amount) => {
+                    self.weaken_demon(target.expect("Targeted effect without a target"), amount);
+                }
+                CardEffect::StunDemon(turns) => {
+                    self.stun_demon(target.expect("Targeted effect without a target"), turns);
+                }
+                CardEffect::DamageDemons(amount) => {
+                    for index in 0..self.demons.len() {
+                        self.damage_demon(index, amount);
+                    }
+                }
+                CardEffect::DamageDemon(amount) => {
+                    self.damage_demon(target.expect("Targeted effect without a target"), amount);
+                }
+            }
+        }
+
+        fn damage_demon(&mut self, index: usize, amount: u32) {
+            let demon = &mut self.demons[index];
+            // Defense soaks up as much of the damage as it can
+            let absorbed = amount.min(demon.defense);
+            demon.defense -= absorbed;
+            let damage = (amount - absorbed).min(demon.health);
+            demon.health -= damage;
+            self.record(CardGameEvent::DamageDemon(index, damage));
+        }
+
+        fn weaken_demon(&mut self, index: usize, amount: u32) {
+            let demon = &mut self.demons[index];
+            // A defending demon shrugs off some of the weakening
+            let absorbed = amount.min(demon.defense);
+            demon.defense -= absorbed;
+            let weakened = (amount - absorbed).min(demon.power);
+            demon.power -= weakened;
+            self.record(CardGameEvent::WeakenDemon(index, weakened));
+        }
+
+        fn stun_demon(&mut self, index: usize, turns: u32) {
+            self.demons[index].stun_time += turns;
+            self.record(CardGameEvent::StunDemon(index, turns));
+        }
+
+        fn has_keyword(&self, card: &Card, keyword: Keyword) -> bool {
+            self.catalogue
+                .get(&card.kind)
+                .is_some_and(|definition| definition.keywords.contains(&keyword))
+        }
+
+        fn exhaust(&mut self, card: Card) {
+            self.exhaust_pile.push(card.clone());
+            self.record(CardGameEvent::ExhaustCard(card));
         }
 
         fn cleanup(&mut self) {
-            // All cards in play are discarded
-            self.discard_pile.append(&mut self.in_play);
+            // Cards in play are discarded, unless they exhaust
+            let in_play: Vec<Card> = self.in_play.drain(..).collect();
+            for card in in_play {
+                if self.has_keyword(&card, Keyword::Exhaust) {
+                    self.exhaust(card);
+                } else {
+                    self.discard_pile.push(card.clone());
+                    self.record(CardGameEvent::DiscardCard(card));
+                }
+            }
+            // Afflictions still in hand take their toll
+            let held: Vec<CardEffect> = self
+                .hand
+                .iter()
+                .filter_map(|card| self.catalogue.get(&card.kind))
+                .flat_map(|definition| definition.while_held.clone())
+                .collect();
+            for effect in held {
+                self.apply_effect(effect, None);
+            }
+            // Then the hand, keeping only the cards that are retained
+            let hand: Vec<Card> = self.hand.drain(..).collect();
+            for card in hand {
+                if self.has_keyword(&card, Keyword::Ethereal) {
+                    self.exhaust(card);
+                } else if self.has_keyword(&card, Keyword::Retain) {
+                    self.hand.push(card);
+                } else {
+                    self.discard_pile.push(card.clone());
+                    self.record(CardGameEvent::DiscardCard(card));
+                }
+            }
         }
 
-        pub fn end_turn(&mut self) {
+        // The fight can end part way through, in which case the rest
+        // of the turn doesn't happen
+        pub fn end_turn(&mut self) -> Result<(), CardGameError> {
+            if self.result.is_some() {
+                return Err(CardGameError::GameOver);
+            }
+            if self.phase != TurnPhase::PlayerMain {
+                return Err(CardGameError::WrongPhase(self.phase));
+            }
+            self.recorded_actions.push(PlayerAction::EndTurn);
+            self.undo_stack.clear();
+            self.enter_phase(TurnPhase::EndOfTurn);
+            self.run_triggers(TurnPhase::EndOfTurn);
             self.cleanup();
+            self.check_result();
+            if self.result.is_some() {
+                return Ok(());
+            }
+            self.enter_phase(TurnPhase::DemonPhase);
             self.demon_attack();
-            // Draw up to 5
-            for _ in 0..(5 - self.hand.len()) {
-                self.draw();
+            self.run_triggers(TurnPhase::DemonPhase);
+            self.check_result();
+            if self.result.is_some() {
+                return Ok(());
             }
+            self.enter_phase(TurnPhase::Draw);
+            // Draw a new hand, less however much the demons hold back
+            let penalty: u32 = self
+                .demons
+                .iter()
+                .filter(|demon| demon.stun_time == 0)
+                .map(|demon| behaviour(demon.kind).draw_penalty(demon))
+                .sum();
+            let hand_size = self.hand_size.saturating_sub(penalty as usize);
+            for _ in 0..hand_size {
+                if self.draw().is_err() {
+                    break;
+                }
+            }
+            self.run_triggers(TurnPhase::Draw);
+            self.check_result();
+            if self.result.is_some() {
+                return Ok(());
+            }
+            self.start_turn();
+            Ok(())
         }
 
         fn demon_attack(&mut self) {
-            for demon in self.demons.iter_mut() {
+            for index in 0..self.demons.len() {
+                let demon = &mut self.demons[index];
+                // Whatever a demon defended with last turn has worn off
+                demon.defense = 0;
                 if demon.stun_time > 0 {
                     demon.stun_time -= 1;
-                } else {
-                    self.player_resolve -= demon.power;
+                    self.record(CardGameEvent::ReduceStun(index));
+                    continue;
+                }
+                match demon.intent.clone() {
+                    Intent::Attack => {
+                        let power = behaviour(demon.kind).attack_damage(
+                            demon,
+                            self.player_resolve,
+                            self.player_max_resolve,
+                        );
+                        self.record(CardGameEvent::DemonAttack(index, power));
+                        // Defense soaks up as much of the attack as it can
+                        let blocked = power.min(self.player_defense);
+                        if blocked > 0 {
+                            self.player_defense -= blocked;
+                            self.record(CardGameEvent::BlockDamage(blocked));
+                        }
+                        let damage = (power - blocked).min(self.player_resolve);
+                        if damage > 0 {
+                            self.player_resolve -= damage;
+                            self.record(CardGameEvent::DamageResolve(damage));
+                        }
+                    }
+                    Intent::Buff(amount) => {
+                        demon.power += amount;
+                        self.record(CardGameEvent::BuffDemon(index, amount));
+                    }
+                    Intent::Defend(amount) => {
+                        demon.defense += amount;
+                        self.record(CardGameEvent::DemonDefend(index, amount));
+                    }
+                    Intent::Inject(kind) => {
+                        if let Err(err) = self.shuffle_into_deck(kind) {
+                            warn!("Demon {} could not inject a card: {}", index, err);
+                        }
+                    }
+                    Intent::Afflict(kind) => {
+                        if let Err(err) = self.gain(kind) {
+                            warn!("Demon {} could not afflict the player: {}", index, err);
+                        }
+                    }
                 }
             }
         }
     }
 
-    pub fn setup(mut commands: Commands, settings: Res<Settings>) {
+    pub fn setup(
+        mut commands: Commands,
+        settings: Res<Settings>,
+        catalogue: Res<CardCatalogue>,
+        run_deck: Res<RunDeck>,
+        resumed: Option<Res<ResumedCombat>>,
+    ) {
+        if let Some(resumed) = resumed {
+            info!("Resuming card game with seed {}", resumed.0.seed);
+            commands.insert_resource(resumed.0.clone());
+            commands.remove_resource::<ResumedCombat>();
+            return;
+        }
+        // Log the seed so the game can be reproduced from a bug report
+        let seed = settings
+            .game
+            .inner
+            .seed
+            .unwrap_or_else(|| thread_rng().gen());
+        info!("Starting card game with seed {}", seed);
         let card_game_model = CardGameModel::new(
-            vec![DemonKind::Fear, DemonKind::Despair, DemonKind::Doubt],
-            vec![
-                CardKind::Inspired,
-                CardKind::Inspired,
-                CardKind::Inspired,
-                CardKind::Peaceful,
-                CardKind::Peaceful,
-                CardKind::Peaceful,
-                CardKind::Peaceful,
-                CardKind::Peaceful,
-                CardKind::Peaceful,
-                CardKind::Peaceful,
-            ],
+            STARTING_DEMONS.to_vec(),
+            run_deck.cards.clone(),
+            &catalogue,
             &settings,
+            seed,
         );
         commands.insert_resource(card_game_model);
     }
 
-    #[derive(Copy, Clone, Debug)]
+    pub fn teardown(mut commands: Commands) {
+        commands.remove_resource::<CardGameModel>();
+        commands.remove_resource::<ReplayPlayback>();
+    }
+
+    // A fight loaded from a save or a replay and already resumed, to pick
+    // up where it was left off instead of starting a new one
+    #[derive(Resource)]
+    pub struct ResumedCombat(pub CardGameModel);
+
+    // Actions from a replay still to be played back, in order. While
+    // this is around the player watches rather than plays
+    #[derive(Resource)]
+    pub struct ReplayPlayback {
+        pub actions: VecDeque<PlayerAction>,
+        // Seconds until the next action
+        pub wait: f32,
+    }
+
+    pub fn is_replaying(playback: Option<Res<ReplayPlayback>>) -> bool {
+        playback.is_some()
+    }
+
+    pub fn is_not_replaying(playback: Option<Res<ReplayPlayback>>) -> bool {
+        playback.is_none()
+    }
+
+    // Every run starts again from the starter deck
+    pub fn new_run(mut commands: Commands, catalogue: Res<CardCatalogue>) {
+        commands.insert_resource(RunDeck::starter(&catalogue));
+    }
+
+    // Forward the model's events to the rest of the game
+    pub fn send_events(
+        mut game_model: ResMut<CardGameModel>,
+        mut ev_card_game: EventWriter<CardGameEvent>,
+        mut ev_update: EventWriter<CardGameUpdate>,
+    ) {
+        for update in game_model.drain_updates() {
+            ev_card_game.send(update.event.clone());
+            ev_update.send(update);
+        }
+    }
+
+    pub fn log_events(mut ev_card_game: EventReader<CardGameEvent>) {
+        for event in ev_card_game.iter() {
+            debug!("{:?}", event);
+        }
+    }
+
+    // Reasons the model can refuse to do what it was asked
+    #[derive(Clone, Debug, Eq, PartialEq)]
+    pub enum CardGameError {
+        // The id of the card that was asked for
+        CardNotInHand(u32),
+        // A card that isn't in the catalogue
+        UnknownCard(CardKind),
+        NotEnoughComposure { cost: u32, available: u32 },
+        // The card was played at a demon that doesn't exist, or the
+        // card needed a demon to target and wasn't given one (or
+        // the other way around)
+        InvalidTarget,
+        // Afflictions that can only be waited out
+        Unplayable,
+        CannotDraw(DrawError),
+        // No card played this turn can be taken back
+        NothingToUndo,
+        // The action can't be taken in the phase the turn is in
+        WrongPhase(TurnPhase),
+        GameOver,
+    }
+
+    // Why a card couldn't be drawn
+    #[derive(Copy, Clone, Debug, Eq, PartialEq)]
+    pub enum DrawError {
+        // The deck and discard pile are both empty
+        NothingToDraw,
+        // The hand already holds as many cards as it can
+        HandFull,
+    }
+
+    impl fmt::Display for DrawError {
+        fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+            match self {
+                DrawError::NothingToDraw => write!(f, "there are no cards left to draw"),
+                DrawError::HandFull => write!(f, "your hand is full"),
+            }
+        }
+    }
+
+    #[derive(Copy, Clone, Debug, Eq, PartialEq, Serialize, Deserialize)]
+    pub enum CombatResult {
+        // Every demon was defeated
+        Victory,
+        // The player ran out of resolve
+        Defeat,
+    }
+
+    impl fmt::Display for CardGameError {
+        fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+            match self {
+                CardGameError::CardNotInHand(card_id) => {
+                    write!(f, "card {} is not in the hand", card_id)
+                }
+                CardGameError::UnknownCard(kind) => write!(f, "there is no card called {}", kind),
+                CardGameError::NotEnoughComposure { cost, available } => {
+                    write!(f, "costs {} composure but only {} is left", cost, available)
+                }
+                CardGameError::InvalidTarget => write!(f, "that is not a valid target"),
+                CardGameError::Unplayable => write!(f, "that card can't be played"),
+                CardGameError::CannotDraw(err) => write!(f, "{}", err),
+                CardGameError::NothingToUndo => write!(f, "there is nothing to take back"),
+                CardGameError::WrongPhase(phase) => write!(f, "not during {:?}", phase),
+                CardGameError::GameOver => write!(f, "the fight is already over"),
+            }
+        }
+    }
+
+    #[derive(Copy, Clone, Debug, Serialize, Deserialize)]
     pub enum DemonKind {
         Fear,
         Despair,
         Doubt,
     }
 
+    #[derive(Clone, Debug, Serialize, Deserialize)]
     pub struct Demon {
         pub kind: DemonKind,
+        // The demon is defeated when this reaches zero
+        pub health: u32,
+        pub max_health: u32,
         // How much damage the demon does to Resolve at the end of the turn
         pub power: u32,
         // How many turns the demon is stunned for
         pub stun_time: u32,
+        // Protects the demon until its next action
+        pub defense: u32,
+        // What the demon will do at the end of the turn
+        pub intent: Intent,
     }
 
-    #[derive(Copy, Clone, Debug, Eq, PartialEq, Hash)]
-    pub enum CardKind {
-        Angry,
-        Inspired,
-        Tired,
-        Stressed,
-        Satisfied,
-        Proud,
-        Determined,
-        Peaceful,
-        Dizzy,
-        Hungover,
+    #[derive(Clone, Debug, Serialize, Deserialize)]
+    pub enum Intent {
+        // Attack the player with the demon's power
+        Attack,
+        // Increase the demon's power
+        Buff(u32),
+        // Gain defense until the demon's next action
+        Defend(u32),
+        // Shuffle a card into the player's deck
+        Inject(CardKind),
+        // Put a card into the player's discard pile
+        Afflict(CardKind),
     }
 
-    #[derive(Clone)]
+    // The rules a kind of demon plays by. Each kind of demon gets its
+    // own implementation, looked up with `behaviour`.
+    pub trait DemonBehaviour {
+        // Shown to the player to explain what makes the demon different
+        fn description(&self) -> &'static str;
+
+        // Decide what the demon will do at the end of the turn
+        fn choose_intent(&self, demon: &Demon, rng: &mut dyn RngCore) -> Intent;
+
+        // How much damage an attack from the demon does
+        fn attack_damage(
+            &self,
+            demon: &Demon,
+            _player_resolve: u32,
+            _player_max_resolve: u32,
+        ) -> u32 {
+            demon.power
+        }
+
+        // How many fewer cards the player draws at the end of the turn
+        fn draw_penalty(&self, _demon: &Demon) -> u32 {
+            0
+        }
+
+        // Effects the demon applies every time a phase comes round,
+        // as long as it isn't stunned
+        fn triggers(&self, _demon: &Demon) -> Vec<(TurnPhase, CardEffect)> {
+            Vec::new()
+        }
+    }
+
+    pub fn behaviour(kind: DemonKind) -> &'static dyn DemonBehaviour {
+        match kind {
+            DemonKind::Fear => &FearBehaviour,
+            DemonKind::Despair => &DespairBehaviour,
+            DemonKind::Doubt => &DoubtBehaviour,
+        }
+    }
+
+    // Fear grows as the player's resolve weakens
+    struct FearBehaviour;
+
+    impl DemonBehaviour for FearBehaviour {
+        fn description(&self) -> &'static str {
+            "Hits harder the lower your resolve, and gnaws at you each turn"
+        }
+
+        fn choose_intent(&self, _demon: &Demon, rng: &mut dyn RngCore) -> Intent {
+            if rng.gen_bool(0.7) {
+                Intent::Attack
+            } else {
+                Intent::Buff(2)
+            }
+        }
+
+        fn attack_damage(
+            &self,
+            demon: &Demon,
+            player_resolve: u32,
+            player_max_resolve: u32,
+        ) -> u32 {
+            // One extra damage for every fifth of resolve that's missing
+            let missing = player_max_resolve.saturating_sub(player_resolve);
+            demon.power + missing * 5 / player_max_resolve.max(1)
+        }
+
+        fn triggers(&self, _demon: &Demon) -> Vec<(TurnPhase, CardEffect)> {
+            vec![(TurnPhase::StartOfTurn, CardEffect::LoseResolve(1))]
+        }
+    }
+
+    // Despair makes it hard to face the next turn
+    struct DespairBehaviour;
+
+    impl DemonBehaviour for DespairBehaviour {
+        fn description(&self) -> &'static str {
+            "You draw one fewer card each turn, and feel tired"
+        }
+
+        fn choose_intent(&self, _demon: &Demon, rng: &mut dyn RngCore) -> Intent {
+            match rng.gen_range(0..20) {
+                0..=11 => Intent::Attack,
+                12..=16 => Intent::Defend(4),
+                _ => Intent::Afflict(CardKind::from(TIRED)),
+            }
+        }
+
+        fn draw_penalty(&self, _demon: &Demon) -> u32 {
+            1
+        }
+    }
+
+    // Doubt fills the player's mind with useless thoughts
+    struct DoubtBehaviour;
+
+    impl DemonBehaviour for DoubtBehaviour {
+        fn description(&self) -> &'static str {
+            "Shuffles junk cards into your deck"
+        }
+
+        fn choose_intent(&self, _demon: &Demon, rng: &mut dyn RngCore) -> Intent {
+            match rng.gen_range(0..20) {
+                0..=9 => Intent::Attack,
+                10..=13 => Intent::Inject(CardKind::from(DIZZY)),
+                14..=16 => Intent::Inject(CardKind::from(STRESSED)),
+                _ => Intent::Defend(3),
+            }
+        }
+    }
+
+    // Card kinds are identified by the name given in their definition
+    #[derive(Clone, Debug, Eq, PartialEq, Hash, Serialize, Deserialize)]
+    #[serde(transparent)]
+    pub struct CardKind(pub String);
+
+    impl From<&str> for CardKind {
+        fn from(name: &str) -> Self {
+            CardKind(name.to_string())
+        }
+    }
+
+    impl fmt::Display for CardKind {
+        fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+            write!(f, "{}", self.0)
+        }
+    }
+
+    #[derive(Copy, Clone, Debug, Eq, PartialEq, Deserialize)]
+    pub enum Rarity {
+        Starter,
+        Common,
+        Uncommon,
+        Rare,
+        // Negative feelings that demons force on the player
+        Affliction,
+    }
+
+    impl Rarity {
+        // How likely a card is to be offered as a reward, relative to
+        // the others. Starter cards and afflictions are never offered.
+        pub fn reward_weight(&self) -> u32 {
+            match self {
+                Rarity::Starter | Rarity::Affliction => 0,
+                Rarity::Common => 6,
+                Rarity::Uncommon => 3,
+                Rarity::Rare => 1,
+            }
+        }
+    }
+
+    // The cards the player carries from one fight to the next
+    #[derive(Resource, Clone, Debug)]
+    pub struct RunDeck {
+        pub cards: Vec<CardKind>,
+    }
+
+    impl RunDeck {
+        pub fn starter(catalogue: &CardCatalogue) -> Self {
+            RunDeck {
+                cards: catalogue.starter_deck(),
+            }
+        }
+
+        pub fn add(&mut self, kind: CardKind) {
+            self.cards.push(kind);
+        }
+    }
+
+    // Everything there is to know about a kind of card, as loaded
+    // from the card catalogue file
+    #[derive(Clone, Debug, Deserialize)]
+    pub struct CardDefinition {
+        pub name: CardKind,
+        pub cost: u32,
+        pub effects: Vec<CardEffect>,
+        // Path of the card art, relative to the assets folder
+        pub image: String,
+        pub description: String,
+        pub rarity: Rarity,
+        #[serde(default)]
+        pub keywords: Vec<Keyword>,
+        // Effects applied at the end of each turn the card is still in hand
+        #[serde(default)]
+        pub while_held: Vec<CardEffect>,
+        // Effects applied every time a phase comes round, for the rest
+        // of the fight once the card has been played
+        #[serde(default)]
+        pub triggers: Vec<(TurnPhase, CardEffect)>,
+        // How many of the card every run starts with
+        #[serde(default)]
+        pub starter_copies: u32,
+    }
+
+    // The steps every turn goes through, in order
+    #[derive(Copy, Clone, Debug, Default, Eq, PartialEq, Serialize, Deserialize)]
+    pub enum TurnPhase {
+        // Defense wears off, composure is restored and demons choose
+        // what they will do
+        #[default]
+        StartOfTurn,
+        // The player plays cards
+        PlayerMain,
+        // Cards in play and in hand are cleaned up
+        EndOfTurn,
+        // Demons act on what they chose at the start of the turn
+        DemonPhase,
+        // The player draws their next hand
+        Draw,
+    }
+
+    // An effect hooked into a phase, and the card that put it there
+    #[derive(Clone, Debug, Serialize, Deserialize)]
+    pub struct Trigger {
+        pub phase: TurnPhase,
+        pub effect: CardEffect,
+        pub source: CardKind,
+    }
+
+    // Rules for how a card moves between zones, on top of its effects
+    #[derive(Copy, Clone, Debug, Eq, PartialEq, Deserialize)]
+    pub enum Keyword {
+        // Goes to the exhaust pile instead of the discard pile once played
+        Exhaust,
+        // Stays in hand at the end of the turn
+        Retain,
+        // Exhausts if it is still in hand at the end of the turn
+        Ethereal,
+        // Always part of the opening hand
+        Innate,
+        // Can't be played at all
+        Unplayable,
+    }
+
+    impl CardDefinition {
+        // Whether the player has to choose a demon for the card to affect
+        pub fn needs_target(&self) -> bool {
+            self.effects.iter().any(|effect| effect.needs_target())
+        }
+    }
+
+    // All the cards that exist in the game
+    #[derive(Resource, Clone, Default)]
+    pub struct CardCatalogue {
+        definitions: Arc<Vec<CardDefinition>>,
+    }
+
+    impl CardCatalogue {
+        pub fn from_file(path: &str) -> Result<Self, CatalogueError> {
+            let contents = fs::read_to_string(path).map_err(CatalogueError::Io)?;
+            let definitions: Vec<CardDefinition> =
+                ron::from_str(&contents).map_err(CatalogueError::Parse)?;
+            let catalogue = Self::from_definitions(definitions)?;
+            // Catch a renamed card now rather than part way into a fight
+            for name in REQUIRED_CARDS {
+                let kind = CardKind::from(name);
+                if catalogue.get(&kind).is_none() {
+                    return Err(CatalogueError::MissingCard(kind));
+                }
+            }
+            if catalogue.starter_deck().is_empty() {
+                return Err(CatalogueError::NoStarterDeck);
+            }
+            Ok(catalogue)
+        }
+
+        pub fn from_definitions(definitions: Vec<CardDefinition>) -> Result<Self, CatalogueError> {
+            for (index, definition) in definitions.iter().enumerate() {
+                if definition.name.0.trim().is_empty() {
+                    return Err(CatalogueError::EmptyName(index));
+                }
+                if definitions[..index]
+                    .iter()
+                    .any(|other| other.name == definition.name)
+                {
+                    return Err(CatalogueError::DuplicateName(definition.name.clone()));
+                }
+                if definition
+                    .triggers
+                    .iter()
+                    .map(|(_, effect)| effect)
+                    .chain(definition.while_held.iter())
+                    .any(|effect| effect.needs_target())
+                {
+                    return Err(CatalogueError::TargetedTrigger(definition.name.clone()));
+                }
+                if !Path::new("assets").join(&definition.image).is_file() {
+                    return Err(CatalogueError::MissingImage(
+                        definition.name.clone(),
+                        definition.image.clone(),
+                    ));
+                }
+            }
+            Ok(CardCatalogue {
+                definitions: Arc::new(definitions),
+            })
+        }
+
+        pub fn get(&self, kind: &CardKind) -> Option<&CardDefinition> {
+            self.definitions.iter().find(|d| d.name == *kind)
+        }
+
+        // Every card's starter copies, in the order they're defined
+        pub fn starter_deck(&self) -> Vec<CardKind> {
+            self.definitions
+                .iter()
+                .flat_map(|definition| {
+                    (0..definition.starter_copies).map(|_| definition.name.clone())
+                })
+                .collect()
+        }
+
+        pub fn iter(&self) -> impl Iterator<Item = &CardDefinition> {
+            self.definitions.iter()
+        }
+    }
+
+    #[derive(Debug)]
+    pub enum CatalogueError {
+        Io(std::io::Error),
+        Parse(ron::error::SpannedError),
+        // The index of the definition with no name
+        EmptyName(usize),
+        DuplicateName(CardKind),
+        // The card and the image path that could not be found
+        MissingImage(CardKind, String),
+        // Effects that happen outside of playing the card have nobody
+        // to choose a demon
+        TargetedTrigger(CardKind),
+        // A card the game needs that isn't defined
+        MissingCard(CardKind),
+        // No card has any starter copies, so runs would start with nothing
+        NoStarterDeck,
+    }
+
+    impl fmt::Display for CatalogueError {
+        fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+            match self {
+                CatalogueError::Io(err) => write!(f, "could not read file: {}", err),
+                CatalogueError::Parse(err) => write!(f, "could not parse file: {}", err),
+                CatalogueError::EmptyName(index) => {
+                    write!(f, "card definition {} has no name", index)
+                }
+                CatalogueError::DuplicateName(kind) => {
+                    write!(f, "card {} is defined more than once", kind)
+                }
+                CatalogueError::MissingImage(kind, image) => {
+                    write!(f, "image {} for card {} does not exist", image, kind)
+                }
+                CatalogueError::TargetedTrigger(kind) => {
+                    write!(f, "card {} has a targeted effect outside of play", kind)
+                }
+                CatalogueError::MissingCard(kind) => {
+                    write!(f, "card {} is used by the game but not defined", kind)
+                }
+                CatalogueError::NoStarterDeck => write!(f, "no card has any starter copies"),
+            }
+        }
+    }
+
+    #[derive(Copy, Clone, Debug, Serialize, Deserialize)]
+    pub enum CardEffect {
+        // Draw cards from the deck
+        Draw(u32),
+        // Discard cards at random from the hand
+        DiscardRandom(u32),
+        // Gain defense against demon attacks
+        GainDefense(u32),
+        // Restore resolve, up to the starting amount
+        Heal(u32),
+        // Lose resolve directly
+        LoseResolve(u32),
+        // Reduce the power of every demon
+        WeakenDemons(u32),
+        // Stun every demon for a number of turns
+        StunDemons(u32),
+        // Reduce the power of the targeted demon
+        WeakenDemon(u32),
+        // Stun the targeted demon for a number of turns
+        StunDemon(u32),
+        // Damage every demon
+        DamageDemons(u32),
+        // Damage the targeted demon
+        DamageDemon(u32),
+    }
+
+    impl CardEffect {
+        pub fn needs_target(&self) -> bool {
+            matches!(
+                self,
+                CardEffect::WeakenDemon(_) | CardEffect::StunDemon(_) | CardEffect::DamageDemon(_)
+            )
+        }
+    }
+
+    #[derive(Clone, Debug, Serialize, Deserialize)]
     pub struct Card {
         pub id: u32,
         pub kind: CardKind,
     }
+
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+
+        fn new_model(cards: Vec<CardKind>, seed: u64) -> CardGameModel {
+            let catalogue = CardCatalogue::from_file(CARD_CATALOGUE_PATH).unwrap();
+            CardGameModel::new(
+                STARTING_DEMONS.to_vec(),
+                cards,
+                &catalogue,
+                &Settings::from_config(),
+                seed,
+            )
+        }
+
+        fn kinds(names: &[&str]) -> Vec<CardKind> {
+            names.iter().map(|name| CardKind::from(*name)).collect()
+        }
+
+        fn ids(cards: &[Card]) -> Vec<u32> {
+            cards.iter().map(|card| card.id).collect()
+        }
+
+        #[test]
+        fn same_seed_same_deck() {
+            let catalogue = CardCatalogue::from_file(CARD_CATALOGUE_PATH).unwrap();
+            let first = new_model(catalogue.starter_deck(), 7);
+            let second = new_model(catalogue.starter_deck(), 7);
+            assert_eq!(ids(&first.deck), ids(&second.deck));
+            assert_eq!(ids(&first.hand), ids(&second.hand));
+        }
+
+        #[test]
+        fn draw_with_nothing_left() {
+            let mut model = new_model(kinds(&["Angry", "Peaceful"]), 1);
+            assert!(model.deck.is_empty() && model.discard_pile.is_empty());
+            assert_eq!(
+                model.draw(),
+                Err(CardGameError::CannotDraw(DrawError::NothingToDraw))
+            );
+            assert_eq!(model.hand.len(), 2);
+        }
+
+        #[test]
+        fn draw_with_full_hand() {
+            let mut model = new_model(kinds(&["Angry"; 20]), 1);
+            while model.hand.len() < model.max_hand_size {
+                let card = model.deck.pop().unwrap();
+                model.hand.push(card);
+            }
+            let deck_size = model.deck.len();
+            assert_eq!(
+                model.draw(),
+                Err(CardGameError::CannotDraw(DrawError::HandFull))
+            );
+            assert_eq!(model.deck.len(), deck_size);
+        }
+
+        #[test]
+        fn no_undo_after_drawing() {
+            let mut cards = kinds(&["Inspired"]);
+            cards.extend(kinds(&["Angry"; 6]));
+            let mut model = new_model(cards, 1);
+            let inspired = model
+                .hand
+                .iter()
+                .find(|card| card.kind == CardKind::from("Inspired"))
+                .unwrap()
+                .id;
+            model.play(inspired, None).unwrap();
+            assert!(matches!(model.undo(), Err(CardGameError::NothingToUndo)));
+        }
+    }
+}
+
+// Ways of choosing what the player does, for anything that plays the
+// card game without a person at the controls
+pub mod strategy {
+
+    use rand::seq::SliceRandom;
+    use rand::{Rng, SeedableRng};
+    use rand_chacha::ChaCha8Rng;
+
+    use super::model::{behaviour, CardGameModel, CombatResult, Intent, PlayerAction};
+
+    pub trait Strategy {
+        fn name(&self) -> &'static str;
+
+        // The next thing to do. Anything returned should be accepted
+        // by the model, and ending the turn always is.
+        fn choose_action(&mut self, model: &CardGameModel) -> PlayerAction;
+    }
+
+    // Every card the player could play right now, with each target it
+    // could be played at
+    pub fn playable_actions(model: &CardGameModel) -> Vec<PlayerAction> {
+        let mut actions = Vec::new();
+        for card in &model.hand {
+            let targets: Vec<Option<usize>> = std::iter::once(None)
+                .chain((0..model.demons.len()).map(Some))
+                .collect();
+            for target in targets {
+                if model.check_play(card.id, target).is_ok() {
+                    actions.push(PlayerAction::Play {
+                        card_id: card.id,
+                        target,
+                    });
+                }
+            }
+        }
+        actions
+    }
+
+    // Plays cards at random until none can be played, then ends the turn
+    pub struct RandomStrategy {
+        rng: ChaCha8Rng,
+    }
+
+    impl RandomStrategy {
+        pub fn new(seed: u64) -> Self {
+            RandomStrategy {
+                rng: ChaCha8Rng::seed_from_u64(seed),
+            }
+        }
+    }
+
+    impl Strategy for RandomStrategy {
+        fn name(&self) -> &'static str {
+            "random"
+        }
+
+        fn choose_action(&mut self, model: &CardGameModel) -> PlayerAction {
+            let actions = playable_actions(model);
+            // Sometimes hold back, so the strategy isn't just greedy
+            if self.rng.gen_bool(0.1) {
+                return PlayerAction::EndTurn;
+            }
+            actions
+                .choose(&mut self.rng)
+                .cloned()
+                .unwrap_or(PlayerAction::EndTurn)
+        }
+    }
+
+    // Tries each card it could play on a copy of the game, and plays
+    // whichever leaves things looking best. Ends the turn once nothing
+    // improves on what it has.
+    pub struct HeuristicStrategy {
+        // Seeds the copies, which mustn't share the game's own rng or
+        // the strategy would know what's coming
+        rng: ChaCha8Rng,
+    }
+
+    impl HeuristicStrategy {
+        pub fn new(seed: u64) -> Self {
+            HeuristicStrategy {
+                rng: ChaCha8Rng::seed_from_u64(seed),
+            }
+        }
+
+        // How good the game looks for the player, higher is better
+        fn score(model: &CardGameModel) -> i64 {
+            match model.result() {
+                Some(CombatResult::Victory) => return i64::MAX,
+                Some(CombatResult::Defeat) => return i64::MIN,
+                None => {}
+            }
+            let incoming: u32 = model
+                .demons
+                .iter()
+                .filter(|demon| demon.stun_time == 0 && matches!(demon.intent, Intent::Attack))
+                .map(|demon| {
+                    behaviour(demon.kind).attack_damage(
+                        demon,
+                        model.player_resolve,
+                        model.player_max_resolve,
+                    )
+                })
+                .sum();
+            let unblocked = incoming.saturating_sub(model.player_defense);
+            let demon_health: u32 = model.demons.iter().map(|demon| demon.health).sum();
+            let demon_power: u32 = model.demons.iter().map(|demon| demon.power).sum();
+            let stunned = model
+                .demons
+                .iter()
+                .filter(|demon| demon.stun_time > 0)
+                .count();
+            // Cards in hand are options for the rest of the turn
+            let options = model.hand.len() as i64;
+            10 * model.player_resolve as i64
+                - 3 * demon_health as i64
+                - 40 * model.demons.len() as i64
+                - 8 * unblocked as i64
+                - 4 * demon_power as i64
+                + 15 * stunned as i64
+                + 2 * options
+        }
+    }
+
+    impl Strategy for HeuristicStrategy {
+        fn name(&self) -> &'static str {
+            "heuristic"
+        }
+
+        fn choose_action(&mut self, model: &CardGameModel) -> PlayerAction {
+            let mut best = (Self::score(model), PlayerAction::EndTurn);
+            // The same luck for every action, so they're compared fairly
+            let seed = self.rng.gen();
+            for action in playable_actions(model) {
+                let mut copy = model.hypothetical(seed);
+                if copy.apply(&action).is_err() {
+                    continue;
+                }
+                let score = Self::score(&copy);
+                if score > best.0 {
+                    best = (score, action);
+                }
+            }
+            best.1
+        }
+    }
+
+    pub fn by_name(name: &str, seed: u64) -> Option<Box<dyn Strategy>> {
+        match name {
+            "random" => Some(Box::new(RandomStrategy::new(seed))),
+            "heuristic" => Some(Box::new(HeuristicStrategy::new(seed))),
+            _ => None,
+        }
+    }
 }
@@ -0,0 +1,5 @@
+pub mod inner;
+pub mod menu;
+pub mod save;
+pub mod settings;
+pub mod state;
@@ -1,13 +1,13 @@
 use bevy::prelude::*;
-use inner::CardGamePlugin;
-
-mod inner;
-mod settings;
-
-use settings::Settings;
+use inner_demons::inner::CardGamePlugin;
+use inner_demons::menu::MenuPlugin;
+use inner_demons::save::SavePlugin;
+use inner_demons::settings::Settings;
+use inner_demons::state::AppState;
 
 fn main() {
-    let settings = Settings::from_config();
+    let mut settings = Settings::from_config();
+    settings.apply_args(std::env::args().skip(1));
     App::new()
         .add_plugins(DefaultPlugins.set(WindowPlugin {
             primary_window: Some(Window {
@@ -20,6 +20,14 @@ fn main() {
             ..default()
         }))
         .insert_resource(settings)
+        .add_state::<AppState>()
+        .add_startup_system(setup_camera)
+        .add_plugin(MenuPlugin)
         .add_plugin(CardGamePlugin)
+        .add_plugin(SavePlugin)
         .run();
 }
+
+fn setup_camera(mut commands: Commands) {
+    commands.spawn(Camera2dBundle::default());
+}
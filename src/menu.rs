@@ -0,0 +1,300 @@
+use bevy::asset::LoadState;
+use bevy::prelude::*;
+
+use std::path::Path;
+
+use crate::inner::model::{CardCatalogue, ReplayPlayback, ResumedCombat, RunDeck};
+use crate::save;
+use crate::settings::Settings;
+use crate::state::{AppState, LoadingHandles};
+
+// Simple screens for the parts of the game that are mostly just a
+// title and some buttons to move on with
+pub struct MenuPlugin;
+
+impl Plugin for MenuPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<LoadingHandles>()
+            .add_system(load_assets.in_schedule(OnEnter(AppState::Loading)))
+            .add_system(check_loading.in_set(OnUpdate(AppState::Loading)))
+            .add_system(setup_main_menu.in_schedule(OnEnter(AppState::MainMenu)))
+            .add_system(despawn_screen.in_schedule(OnExit(AppState::MainMenu)))
+            .add_system(setup_overworld.in_schedule(OnEnter(AppState::Overworld)))
+            .add_system(despawn_screen.in_schedule(OnExit(AppState::Overworld)))
+            .add_system(setup_game_over.in_schedule(OnEnter(AppState::GameOver)))
+            .add_system(despawn_screen.in_schedule(OnExit(AppState::GameOver)))
+            .add_system(menu_btn_interaction);
+    }
+}
+
+#[derive(Resource)]
+struct MenuFont(Handle<Font>);
+
+// Root of whichever screen is currently shown
+#[derive(Component)]
+struct Screen;
+
+// Text under the buttons saying why the last one didn't work
+#[derive(Component)]
+struct MenuMessage;
+
+// What a menu button does when clicked
+#[derive(Component)]
+enum MenuBtn {
+    // Move the game to another state
+    Goto(AppState),
+    // Load the saved fight and carry on with it
+    Continue,
+    // Watch the replay given in the settings
+    Replay,
+}
+
+fn load_assets(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    mut loading: ResMut<LoadingHandles>,
+) {
+    let font = asset_server.load("fonts/DejaVuSansCondensed.ttf");
+    loading.0.push(font.clone_untyped());
+    commands.insert_resource(MenuFont(font));
+}
+
+fn check_loading(
+    asset_server: Res<AssetServer>,
+    mut loading: ResMut<LoadingHandles>,
+    mut next_state: ResMut<NextState<AppState>>,
+) {
+    match asset_server.get_group_load_state(loading.0.iter().map(|handle| handle.id())) {
+        LoadState::Loaded => {}
+        LoadState::Failed => {
+            // Carry on without whatever is missing rather than get stuck,
+            // but say what it was
+            for handle in &loading.0 {
+                if asset_server.get_load_state(handle.id()) == LoadState::Failed {
+                    match asset_server.get_handle_path(handle) {
+                        Some(path) => error!("Could not load {}", path.path().display()),
+                        None => error!("Could not load an asset"),
+                    }
+                }
+            }
+        }
+        _ => return,
+    }
+    loading.0.clear();
+    next_state.set(AppState::MainMenu);
+}
+
+fn spawn_screen(
+    commands: &mut Commands,
+    font: &MenuFont,
+    title: &str,
+    buttons: Vec<(&str, MenuBtn)>,
+) {
+    commands
+        .spawn(NodeBundle {
+            style: Style {
+                size: Size::all(Val::Percent(100.0)),
+                flex_direction: FlexDirection::Column,
+                justify_content: JustifyContent::Center,
+                align_items: AlignItems::Center,
+                ..default()
+            },
+            background_color: Color::rgb(0.1, 0.05, 0.2).into(),
+            ..default()
+        })
+        .insert(Screen)
+        .with_children(|screen| {
+            screen.spawn(TextBundle::from_section(
+                title,
+                TextStyle {
+                    font: font.0.clone(),
+                    font_size: 64.0,
+                    color: Color::WHITE,
+                },
+            ));
+            for (label, menu_btn) in buttons {
+                screen
+                    .spawn(ButtonBundle {
+                        style: Style {
+                            size: Size::new(Val::Px(240.0), Val::Px(50.0)),
+                            margin: UiRect {
+                                top: Val::Px(20.0),
+                                ..default()
+                            },
+                            justify_content: JustifyContent::Center,
+                            align_items: AlignItems::Center,
+                            ..default()
+                        },
+                        background_color: Color::rgb(0.3, 0.1, 0.7).into(),
+                        ..default()
+                    })
+                    .insert(menu_btn)
+                    .with_children(|button| {
+                        button.spawn(TextBundle::from_section(
+                            label,
+                            TextStyle {
+                                font: font.0.clone(),
+                                font_size: 32.0,
+                                color: Color::WHITE,
+                            },
+                        ));
+                    });
+            }
+            screen
+                .spawn(
+                    TextBundle::from_section(
+                        "",
+                        TextStyle {
+                            font: font.0.clone(),
+                            font_size: 24.0,
+                            color: Color::rgb(1.0, 0.6, 0.6),
+                        },
+                    )
+                    .with_style(Style {
+                        margin: UiRect {
+                            top: Val::Px(20.0),
+                            ..default()
+                        },
+                        ..default()
+                    }),
+                )
+                .insert(MenuMessage);
+        });
+}
+
+fn setup_main_menu(mut commands: Commands, font: Res<MenuFont>, settings: Res<Settings>) {
+    let mut buttons = Vec::new();
+    if save::exists() {
+        buttons.push(("Continue", MenuBtn::Continue));
+    }
+    if settings.game.inner.replay.is_some() {
+        buttons.push(("Watch replay", MenuBtn::Replay));
+    }
+    buttons.push(("Start", MenuBtn::Goto(AppState::Overworld)));
+    spawn_screen(&mut commands, &font, "Inner Demons", buttons);
+}
+
+fn setup_overworld(mut commands: Commands, font: Res<MenuFont>) {
+    // The outer game isn't playable yet, so this just leads to a fight
+    spawn_screen(
+        &mut commands,
+        &font,
+        "The world outside",
+        vec![("Face your demons", MenuBtn::Goto(AppState::CardCombat))],
+    );
+}
+
+fn setup_game_over(mut commands: Commands, font: Res<MenuFont>) {
+    spawn_screen(
+        &mut commands,
+        &font,
+        "Your demons got the better of you",
+        vec![("Main menu", MenuBtn::Goto(AppState::MainMenu))],
+    );
+}
+
+fn despawn_screen(mut commands: Commands, q_screen: Query<Entity, With<Screen>>) {
+    for entity in &q_screen {
+        commands.entity(entity).despawn_recursive();
+    }
+}
+
+type MenuBtnInteractionQuery<'w, 's> = Query<
+    'w,
+    's,
+    (
+        &'static Interaction,
+        &'static mut BackgroundColor,
+        &'static MenuBtn,
+    ),
+    (Changed<Interaction>, With<Button>),
+>;
+
+// The saved fight and the deck it was fought with, resumed and ready
+// to carry on with
+fn load_saved_fight(catalogue: &CardCatalogue) -> Result<(RunDeck, ResumedCombat), String> {
+    let mut save_file = save::load().map_err(|err| err.to_string())?;
+    save_file
+        .model
+        .resume(catalogue)
+        .map_err(|err| err.to_string())?;
+    Ok((
+        RunDeck {
+            cards: save_file.run_deck,
+        },
+        ResumedCombat(save_file.model),
+    ))
+}
+
+// The fight at the start of a replay, resumed and ready to play the
+// replay's actions back on
+fn load_replay(
+    path: &str,
+    catalogue: &CardCatalogue,
+) -> Result<(ResumedCombat, ReplayPlayback), String> {
+    let mut replay = save::load_replay(Path::new(path)).map_err(|err| err.to_string())?;
+    replay
+        .start
+        .resume(catalogue)
+        .map_err(|err| err.to_string())?;
+    info!("Watching replay of the fight with seed {}", replay.seed);
+    Ok((
+        ResumedCombat(replay.start),
+        ReplayPlayback {
+            actions: replay.actions.into(),
+            wait: 0.0,
+        },
+    ))
+}
+
+fn menu_btn_interaction(
+    mut commands: Commands,
+    mut q_interaction: MenuBtnInteractionQuery,
+    mut q_message: Query<&mut Text, With<MenuMessage>>,
+    mut next_state: ResMut<NextState<AppState>>,
+    settings: Res<Settings>,
+    catalogue: Res<CardCatalogue>,
+) {
+    for (interaction, mut color, menu_btn) in &mut q_interaction {
+        match *interaction {
+            Interaction::Clicked => {
+                let result = match menu_btn {
+                    MenuBtn::Goto(state) => {
+                        next_state.set(*state);
+                        Ok(())
+                    }
+                    MenuBtn::Continue => match load_saved_fight(&catalogue) {
+                        Ok((run_deck, resumed)) => {
+                            commands.insert_resource(run_deck);
+                            commands.insert_resource(resumed);
+                            next_state.set(AppState::CardCombat);
+                            Ok(())
+                        }
+                        Err(err) => Err(format!("Could not load the saved fight: {}", err)),
+                    },
+                    MenuBtn::Replay => {
+                        let path = settings.game.inner.replay.as_deref().unwrap_or_default();
+                        match load_replay(path, &catalogue) {
+                            Ok((resumed, playback)) => {
+                                commands.insert_resource(resumed);
+                                commands.insert_resource(playback);
+                                next_state.set(AppState::CardCombat);
+                                Ok(())
+                            }
+                            Err(err) => Err(format!("Could not load the replay {}: {}", path, err)),
+                        }
+                    }
+                };
+                // Stay on the menu and say what went wrong
+                if let Err(message) = result {
+                    warn!("{}", message);
+                    for mut text in &mut q_message {
+                        text.sections[0].value = message.clone();
+                    }
+                }
+            }
+            Interaction::Hovered => *color = Color::rgb(0.4, 0.2, 0.8).into(),
+            Interaction::None => *color = Color::rgb(0.3, 0.1, 0.7).into(),
+        }
+    }
+}
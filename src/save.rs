@@ -0,0 +1,343 @@
+use std::fmt;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::inner::model::{self, CardGameModel, CardKind, PlayerAction, RunDeck, TurnPhase};
+use crate::inner::{self, CardGameEvent};
+use crate::state::AppState;
+
+// Bump whenever the saved model changes shape, so old saves are
+// refused instead of being misread
+pub const SAVE_VERSION: u32 = 1;
+
+const SAVE_FILE_NAME: &str = "combat.ron";
+
+// The same, for replays
+pub const REPLAY_VERSION: u32 = 1;
+
+// Folder in the data directory that replays are recorded to
+const REPLAY_DIR_NAME: &str = "replays";
+
+// Keeps a fight in progress on disk so it survives quitting the game,
+// along with a replay of it so far. Replays being watched and fights
+// the computer is playing are left alone
+pub struct SavePlugin;
+
+impl Plugin for SavePlugin {
+    fn build(&self, app: &mut App) {
+        app.add_system(
+            autosave
+                .after(model::send_events)
+                .run_if(model::is_not_replaying)
+                .run_if(inner::is_not_autoplaying)
+                .in_set(OnUpdate(AppState::CardCombat)),
+        )
+        .add_system(
+            record_replay
+                .after(model::send_events)
+                .run_if(model::is_not_replaying)
+                .run_if(inner::is_not_autoplaying)
+                .in_set(OnUpdate(AppState::CardCombat)),
+        );
+    }
+}
+
+#[derive(Deserialize)]
+pub struct SaveFile {
+    pub version: u32,
+    pub run_deck: Vec<CardKind>,
+    pub model: CardGameModel,
+}
+
+// The same as SaveFile, but borrowing what it saves
+#[derive(Serialize)]
+struct SaveFileRef<'a> {
+    version: u32,
+    run_deck: &'a [CardKind],
+    model: &'a CardGameModel,
+}
+
+// A fight from where it started, or was resumed from, and everything
+// the player did in it. Played back in order the actions give the same
+// fight again, since all its randomness comes from the saved rng
+#[derive(Deserialize)]
+pub struct Replay {
+    pub version: u32,
+    pub seed: u64,
+    pub start: CardGameModel,
+    pub actions: Vec<PlayerAction>,
+}
+
+#[derive(Serialize)]
+struct ReplayRef<'a> {
+    version: u32,
+    seed: u64,
+    start: &'a CardGameModel,
+    actions: &'a [PlayerAction],
+}
+
+// Only the version, read first so a save from another version can be
+// told apart from a broken one
+#[derive(Deserialize)]
+struct SaveHeader {
+    version: u32,
+}
+
+#[derive(Debug)]
+pub enum SaveError {
+    // There's nowhere to keep user data on this system
+    NoDataDir,
+    Io(std::io::Error),
+    Serialize(ron::Error),
+    Parse(ron::error::SpannedError),
+    // The file was written with another version than this one reads
+    WrongVersion { found: u32, expected: u32 },
+}
+
+impl fmt::Display for SaveError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            SaveError::NoDataDir => write!(f, "could not find a user data directory"),
+            SaveError::Io(err) => write!(f, "could not access file: {}", err),
+            SaveError::Serialize(err) => write!(f, "could not write file: {}", err),
+            SaveError::Parse(err) => write!(f, "could not read file: {}", err),
+            SaveError::WrongVersion { found, expected } => write!(
+                f,
+                "file is from version {} but version {} is needed",
+                found, expected
+            ),
+        }
+    }
+}
+
+// Where the platform likes applications to keep their data
+fn data_dir() -> Option<PathBuf> {
+    let env_dir = |name: &str| std::env::var_os(name).map(PathBuf::from);
+    let base = if cfg!(target_os = "windows") {
+        env_dir("APPDATA")
+    } else if cfg!(target_os = "macos") {
+        env_dir("HOME").map(|home| home.join("Library/Application Support"))
+    } else {
+        env_dir("XDG_DATA_HOME").or_else(|| env_dir("HOME").map(|home| home.join(".local/share")))
+    };
+    base.map(|base| base.join("inner_demons"))
+}
+
+pub fn save_path() -> Result<PathBuf, SaveError> {
+    data_dir()
+        .map(|dir| dir.join(SAVE_FILE_NAME))
+        .ok_or(SaveError::NoDataDir)
+}
+
+pub fn exists() -> bool {
+    save_path().is_ok_and(|path| path.is_file())
+}
+
+// Where to record a fight starting now, named by when it started and
+// its seed so every fight gets a replay of its own
+pub fn replay_path(seed: u64) -> Result<PathBuf, SaveError> {
+    let started = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |time| time.as_secs());
+    data_dir()
+        .map(|dir| {
+            dir.join(REPLAY_DIR_NAME)
+                .join(format!("{}-{}.ron", started, seed))
+        })
+        .ok_or(SaveError::NoDataDir)
+}
+
+fn write_ron(path: &Path, value: &impl Serialize) -> Result<(), SaveError> {
+    let text = ron::ser::to_string_pretty(value, ron::ser::PrettyConfig::default())
+        .map_err(SaveError::Serialize)?;
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir).map_err(SaveError::Io)?;
+    }
+    // Write next to the file and swap it in, so quitting part way
+    // through never leaves a broken file behind
+    let temp_path = path.with_extension("ron.tmp");
+    fs::write(&temp_path, text).map_err(SaveError::Io)?;
+    fs::rename(&temp_path, path).map_err(SaveError::Io)
+}
+
+fn read_ron<T: for<'de> Deserialize<'de>>(path: &Path, expected: u32) -> Result<T, SaveError> {
+    let text = fs::read_to_string(path).map_err(SaveError::Io)?;
+    let header: SaveHeader = ron::from_str(&text).map_err(SaveError::Parse)?;
+    if header.version != expected {
+        return Err(SaveError::WrongVersion {
+            found: header.version,
+            expected,
+        });
+    }
+    ron::from_str(&text).map_err(SaveError::Parse)
+}
+
+pub fn save(run_deck: &RunDeck, model: &CardGameModel) -> Result<(), SaveError> {
+    write_ron(
+        &save_path()?,
+        &SaveFileRef {
+            version: SAVE_VERSION,
+            run_deck: &run_deck.cards,
+            model,
+        },
+    )
+}
+
+pub fn load() -> Result<SaveFile, SaveError> {
+    read_ron(&save_path()?, SAVE_VERSION)
+}
+
+// Write the model's recording so far, if it has one
+pub fn save_replay(path: &Path, model: &CardGameModel) -> Result<(), SaveError> {
+    match model.recording() {
+        Some((start, actions)) => write_ron(
+            path,
+            &ReplayRef {
+                version: REPLAY_VERSION,
+                seed: start.seed,
+                start,
+                actions,
+            },
+        ),
+        None => Ok(()),
+    }
+}
+
+pub fn load_replay(path: &Path) -> Result<Replay, SaveError> {
+    read_ron(path, REPLAY_VERSION)
+}
+
+pub fn delete() -> Result<(), SaveError> {
+    let path = save_path()?;
+    if path.is_file() {
+        fs::remove_file(path).map_err(SaveError::Io)?;
+    }
+    Ok(())
+}
+
+// Save whenever the player gets a new turn, and throw the save away
+// once the fight is over
+fn autosave(
+    mut ev_card_game: EventReader<CardGameEvent>,
+    game_model: Res<CardGameModel>,
+    run_deck: Res<RunDeck>,
+) {
+    let mut new_turn = false;
+    let mut combat_over = false;
+    for event in ev_card_game.iter() {
+        match event {
+            CardGameEvent::EnterPhase(TurnPhase::PlayerMain) => new_turn = true,
+            CardGameEvent::CombatOver(_) => combat_over = true,
+            _ => {}
+        }
+    }
+    let result = if combat_over {
+        delete()
+    } else if new_turn {
+        save(&run_deck, &game_model)
+    } else {
+        return;
+    };
+    if let Err(err) = result {
+        warn!("Autosave failed: {}", err);
+    }
+}
+
+// Keep the replay on disk up to date with every action, so it's there
+// to attach to a bug report whenever something goes wrong
+fn record_replay(
+    game_model: Res<CardGameModel>,
+    mut path: Local<Option<PathBuf>>,
+    mut recorded: Local<Option<usize>>,
+) {
+    if game_model.is_added() {
+        *recorded = None;
+        *path = match replay_path(game_model.seed) {
+            Ok(new_path) => {
+                info!("Recording replay to {}", new_path.display());
+                Some(new_path)
+            }
+            Err(err) => {
+                warn!("Could not record replay: {}", err);
+                None
+            }
+        };
+    }
+    let path = match path.as_ref() {
+        Some(path) => path,
+        None => return,
+    };
+    let actions = game_model
+        .recording()
+        .map_or(0, |(_, actions)| actions.len());
+    if *recorded == Some(actions) {
+        return;
+    }
+    *recorded = Some(actions);
+    if let Err(err) = save_replay(path, &game_model) {
+        warn!("Could not write replay: {}", err);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::{Rng, SeedableRng};
+    use rand_chacha::ChaCha8Rng;
+
+    use super::*;
+    use crate::inner::model::{CardCatalogue, CARD_CATALOGUE_PATH, STARTING_DEMONS};
+    use crate::inner::strategy::{RandomStrategy, Strategy};
+    use crate::settings::Settings;
+
+    // Fights played with undos thrown in play back, once written out
+    // and read in again, to exactly where they ended up
+    #[test]
+    fn replay_reproduces_fight() {
+        let catalogue = CardCatalogue::from_file(CARD_CATALOGUE_PATH).unwrap();
+        let settings = Settings::from_config();
+        let path = std::env::temp_dir().join(format!("inner_demons_{}.ron", std::process::id()));
+        let mut undos = 0;
+        for seed in 0..200 {
+            let mut model = CardGameModel::new(
+                STARTING_DEMONS.to_vec(),
+                catalogue.starter_deck(),
+                &catalogue,
+                &settings,
+                seed,
+            );
+            let mut strategy = RandomStrategy::new(seed);
+            let mut rng = ChaCha8Rng::seed_from_u64(seed);
+            for _ in 0..500 {
+                if model.result().is_some() {
+                    break;
+                }
+                if rng.gen_bool(0.2) && model.undo().is_ok() {
+                    undos += 1;
+                    continue;
+                }
+                let action = strategy.choose_action(&model);
+                model.apply(&action).unwrap();
+            }
+            save_replay(&path, &model).unwrap();
+            let replay = load_replay(&path).unwrap();
+            assert_eq!(replay.seed, seed);
+            let mut played_back = replay.start;
+            played_back.resume(&catalogue).unwrap();
+            for action in &replay.actions {
+                played_back.apply(action).unwrap();
+            }
+            assert_eq!(
+                format!("{:?}", played_back.table()),
+                format!("{:?}", model.table()),
+                "seed {}",
+                seed
+            );
+        }
+        fs::remove_file(path).unwrap();
+        assert!(undos > 0);
+    }
+}
@@ -6,6 +6,7 @@ use std::collections::HashMap;
 pub struct Settings {
     pub window: WindowSettings,
     pub game: GameSettings,
+    pub presentation: PresentationSettings,
 }
 
 pub struct WindowSettings {
@@ -13,6 +14,18 @@ pub struct WindowSettings {
     pub height: f32,
 }
 
+// How many seconds each kind of card game event is shown for
+pub struct PresentationSettings {
+    pub card_move: f32,
+    pub pile_move: f32,
+    pub demon_action: f32,
+    pub effect: f32,
+    pub combat_over: f32,
+    pub feedback: f32,
+    pub autoplay_delay: f32,
+    pub autoplay_screen_delay: f32,
+}
+
 pub struct GameSettings {
     pub inner: InnerSettings,
     pub outer: OuterSettings,
@@ -20,8 +33,18 @@ pub struct GameSettings {
 
 pub struct InnerSettings {
     pub starting_resolve: u32,
+    pub starting_demon_health: u32,
     pub starting_demon_power: u32,
     pub starting_demon_stun_time: u32,
+    pub composure_per_turn: u32,
+    pub hand_size: usize,
+    pub max_hand_size: usize,
+    pub defense_retained_percent: u32,
+    // Seed for the card game's random number generator, random if not set
+    pub seed: Option<u64>,
+    pub autoplay: bool,
+    // Replay file that can be watched from the main menu, if any
+    pub replay: Option<String>,
 }
 
 pub struct OuterSettings {
@@ -48,6 +71,11 @@ impl Settings {
                         .unwrap()
                         .parse()
                         .unwrap(),
+                    starting_demon_health: config
+                        .get("game__inner__starting_demon_health")
+                        .unwrap()
+                        .parse()
+                        .unwrap(),
                     starting_demon_power: config
                         .get("game__inner__starting_demon_power")
                         .unwrap()
@@ -58,6 +86,35 @@ impl Settings {
                         .unwrap()
                         .parse()
                         .unwrap(),
+                    composure_per_turn: config
+                        .get("game__inner__composure_per_turn")
+                        .unwrap()
+                        .parse()
+                        .unwrap(),
+                    hand_size: config
+                        .get("game__inner__hand_size")
+                        .unwrap()
+                        .parse()
+                        .unwrap(),
+                    max_hand_size: config
+                        .get("game__inner__max_hand_size")
+                        .unwrap()
+                        .parse()
+                        .unwrap(),
+                    defense_retained_percent: config
+                        .get("game__inner__defense_retained_percent")
+                        .unwrap()
+                        .parse()
+                        .unwrap(),
+                    seed: config
+                        .get("game__inner__seed")
+                        .map(|seed| seed.parse().unwrap()),
+                    autoplay: config
+                        .get("game__inner__autoplay")
+                        .unwrap()
+                        .parse()
+                        .unwrap(),
+                    replay: config.get("game__inner__replay").cloned(),
                 },
                 outer: OuterSettings {
                     starting_health: config
@@ -67,6 +124,58 @@ impl Settings {
                         .unwrap(),
                 },
             },
+            presentation: PresentationSettings {
+                card_move: config
+                    .get("presentation__card_move")
+                    .unwrap()
+                    .parse()
+                    .unwrap(),
+                pile_move: config
+                    .get("presentation__pile_move")
+                    .unwrap()
+                    .parse()
+                    .unwrap(),
+                demon_action: config
+                    .get("presentation__demon_action")
+                    .unwrap()
+                    .parse()
+                    .unwrap(),
+                effect: config.get("presentation__effect").unwrap().parse().unwrap(),
+                combat_over: config
+                    .get("presentation__combat_over")
+                    .unwrap()
+                    .parse()
+                    .unwrap(),
+                feedback: config
+                    .get("presentation__feedback")
+                    .unwrap()
+                    .parse()
+                    .unwrap(),
+                autoplay_delay: config
+                    .get("presentation__autoplay_delay")
+                    .unwrap()
+                    .parse()
+                    .unwrap(),
+                autoplay_screen_delay: config
+                    .get("presentation__autoplay_screen_delay")
+                    .unwrap()
+                    .parse()
+                    .unwrap(),
+            },
+        }
+    }
+
+    // Override settings with any that were given on the command line
+    pub fn apply_args(&mut self, mut args: impl Iterator<Item = String>) {
+        while let Some(arg) = args.next() {
+            if arg == "--seed" {
+                let seed = args.next().expect("--seed needs a value");
+                self.game.inner.seed = Some(seed.parse().expect("--seed must be a number"));
+            } else if arg == "--autoplay" {
+                self.game.inner.autoplay = true;
+            } else if arg == "--replay" {
+                self.game.inner.replay = Some(args.next().expect("--replay needs a file"));
+            }
         }
     }
 }
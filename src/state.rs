@@ -0,0 +1,18 @@
+use bevy::prelude::*;
+
+// The parts of the game the player moves between
+#[derive(States, Debug, Clone, Copy, Default, Eq, PartialEq, Hash)]
+pub enum AppState {
+    #[default]
+    Loading,
+    MainMenu,
+    Overworld,
+    CardCombat,
+    Reward,
+    GameOver,
+}
+
+// Assets that have to finish loading before the game leaves the
+// Loading state. Plugins add their handles on entering Loading.
+#[derive(Resource, Default)]
+pub struct LoadingHandles(pub Vec<HandleUntyped>);